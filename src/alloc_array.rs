@@ -1,7 +1,23 @@
-use core::mem::MaybeUninit;
-use alloc::vec::Vec;
+use core::{
+    alloc::Layout,
+    fmt,
+    mem::{MaybeUninit, size_of},
+    ptr::NonNull,
+    slice::from_raw_parts_mut,
+};
+use alloc::{alloc::handle_alloc_error, vec::Vec};
 
-use crate::try_inplace_array;
+use crate::{
+    try_inplace_array,
+    try_inplace_array_with_size_classes,
+    DEFAULT_STACK_LIMIT_BYTES,
+};
+use crate::size_classes::{SizeClasses, DefaultSizeClasses, max_default_class_size};
+use crate::scratch_allocator::ScratchAllocator;
+#[cfg(not(feature = "scratch-pool"))]
+use crate::scratch_allocator::Global as DefaultScratchAllocator;
+#[cfg(feature = "scratch-pool")]
+use crate::scratch_pool::ThreadScratchPool as DefaultScratchAllocator;
 use crate::guards::UninitializedSliceMemoryGuard;
 
 /// `alloc_array` is used when `inplace_or_alloc_array` realize that the size of requested array of `T`
@@ -10,13 +26,130 @@ use crate::guards::UninitializedSliceMemoryGuard;
 /// It allocates a vector with `size` elements and fills it up with help of `init` closure
 /// and then pass a reference to a slice of the vector into the `consumer` closure.
 /// `consumer`'s result will be returned.
+///
+/// The array is allocated with `Global` allocator or,
+/// with `scratch-pool` feature enabled, with `ThreadScratchPool` which reuses memory of previous calls.
+///
+/// ### Panics
+///
+/// Panic can be reached when `size` items of `T` take more than `isize::MAX` bytes.
 pub fn alloc_array<T, R, Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R>(size: usize, consumer: Consumer) -> R {
-    unsafe {
-        let mut memory_holder = Vec::<MaybeUninit<T>>::with_capacity(size);
-        memory_holder.set_len(size);
-        let result = consumer(UninitializedSliceMemoryGuard::new(&mut *memory_holder));
-        memory_holder.set_len(0);
-        result
+    alloc_array_in(&DefaultScratchAllocator, size, consumer)
+}
+
+/// `alloc_array_in` works like `alloc_array` but allocates the array with given `allocator`.
+///
+/// ### Panics
+///
+/// Panic can be reached when `size` items of `T` take more than `isize::MAX` bytes.
+/// If `allocator` fails, `alloc::alloc::handle_alloc_error` will be called.
+pub fn alloc_array_in<T, R, A, Consumer>(allocator: &A, size: usize, consumer: Consumer) -> R
+    where A: ScratchAllocator + ?Sized,
+          Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R,
+{
+    match try_alloc_array_in(allocator, size, consumer) {
+        Ok(result) => result,
+        Err((AllocArrayError::CapacityOverflow, _)) => panic!("capacity overflow"),
+        Err((AllocArrayError::AllocFailed { layout }, _)) => handle_alloc_error(layout),
+    }
+}
+
+/// Reason why `try_alloc_array` cannot allocate an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocArrayError {
+    /// Requested items take more than `isize::MAX` bytes.
+    CapacityOverflow,
+    /// The allocator failed to allocate memory of requested `layout`.
+    AllocFailed {
+        /// Layout of requested array.
+        layout: Layout,
+    },
+}
+
+impl fmt::Display for AllocArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocArrayError::CapacityOverflow => f.write_str("capacity overflow"),
+            AllocArrayError::AllocFailed { layout } => write!(f, "memory allocation of {} bytes failed", layout.size()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AllocArrayError {}
+
+/// `try_alloc_array` works like `alloc_array` but does not panic or abort if the array cannot be allocated.
+/// `Err((error, consumer))` will be returned instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{try_alloc_array, AllocArrayError, UninitializedSliceMemoryGuard};
+///
+/// let result = try_alloc_array(usize::MAX, |guard: UninitializedSliceMemoryGuard<u32>| guard.len());
+/// match result {
+///     Err((AllocArrayError::CapacityOverflow, consumer)) => {
+///         // The consumer is still available, so it can be called with a smaller array
+///         assert_eq!(try_alloc_array(10, consumer).ok(), Some(10));
+///     }
+///     _ => unreachable!("usize::MAX items of u32 cannot be allocated"),
+/// }
+/// ```
+pub fn try_alloc_array<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, (AllocArrayError, Consumer)>
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    try_alloc_array_in(&DefaultScratchAllocator, size, consumer)
+}
+
+/// `try_alloc_array_in` works like `try_alloc_array` but allocates the array with given `allocator`.
+pub fn try_alloc_array_in<T, R, A, Consumer>(allocator: &A, size: usize, consumer: Consumer) -> Result<R, (AllocArrayError, Consumer)>
+    where A: ScratchAllocator + ?Sized,
+          Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R,
+{
+    let layout = match Layout::array::<T>(size) {
+        Ok(layout) => layout,
+        Err(_) => return Err((AllocArrayError::CapacityOverflow, consumer)),
+    };
+    let mut memory = match ScratchMemory::allocate(allocator, size, layout) {
+        Some(memory) => memory,
+        None => return Err((AllocArrayError::AllocFailed { layout: allocator.allocated_layout(layout) }, consumer)),
+    };
+    Ok(consumer(unsafe { UninitializedSliceMemoryGuard::new(memory.as_slice()) }))
+}
+
+/// Array of `T` allocated with `A`. Deallocates the memory on drop.
+struct ScratchMemory<'a, T, A: ScratchAllocator + ?Sized> {
+    allocator: &'a A,
+    ptr: NonNull<MaybeUninit<T>>,
+    len: usize,
+    layout: Layout,
+}
+
+impl<'a, T, A: ScratchAllocator + ?Sized> ScratchMemory<'a, T, A> {
+    /// Allocates `len` items of `T` described by `layout`.
+    /// Zero-sized layouts are not allocated at all.
+    #[inline]
+    fn allocate(allocator: &'a A, len: usize, layout: Layout) -> Option<Self> {
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            allocator.allocate(layout)?.cast()
+        };
+        Some(Self { allocator, ptr, len, layout })
+    }
+
+    #[inline]
+    fn as_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        unsafe { from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<'a, T, A: ScratchAllocator + ?Sized> Drop for ScratchMemory<'a, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { self.allocator.deallocate(self.ptr.cast(), self.layout) }
+        }
     }
 }
 
@@ -24,14 +157,21 @@ pub fn alloc_array<T, R, Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
 ///  It's trying to place an array of `T` on the stack and pass the guard of memory into the
 /// `consumer` closure. `consumer`'s result will be returned.
 ///
-/// If the result of array of `T` is more than 4096 then the vector will be allocated
+/// If the array of `T` takes more than `DEFAULT_STACK_LIMIT_BYTES` bytes (16 KiB by default)
+/// then the vector will be allocated
 /// in the heap and will be used instead of stack-based fixed-size array.
+/// Use `inplace_or_alloc_array_with_limit` to choose another limit.
+/// With `std` feature enabled the vector will be allocated also if placing the array
+/// would leave less than `stack_safety_margin()` bytes of the current thread's stack
+/// or if the current thread's stack budget is run out (see `set_thread_stack_budget`).
 ///
 /// Sometimes size of allocated array might be more than requested. For sizes larger than 32,
 /// the following formula is used: `roundUp(size/32)*32`. This is a simplification that used
 /// for keeping code short, simple and able to optimize.
 /// For example, for requested 50 item `[T; 64]` will be allocated.
 /// For 120 items - `[T; 128]` and so on.
+/// Sizes larger than 4096 are rounded up to the next power of two.
+/// With `alloca` feature enabled arrays are not rounded up on x86_64 and aarch64 Linux (see `Alloca`).
 ///
 /// Note that rounding size up is working for fixed-sized arrays only. If function decides to
 /// allocate a vector then its size will be equal to requested.
@@ -45,6 +185,7 @@ pub fn alloc_array<T, R, Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
 /// };
 ///
 /// let sum: u16 = inplace_or_alloc_array(100, |uninit_guard: UninitializedSliceMemoryGuard<u16>| {
+///     # #[cfg(not(feature = "alloca"))]
 ///     assert_eq!(uninit_guard.len(), 128);
 ///     // For now, our memory is placed/allocated but uninitialized.
 ///     // Let's initialize it!
@@ -53,25 +194,124 @@ pub fn alloc_array<T, R, Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
 ///     guard.iter().sum()
 /// });
 /// // Sum of [0, 2, 4, 6, ..., 252, 254] = sum of [0, 1, 2, 3, ..., 126, 127] * 2 = ( 127 * (127+1) ) / 2 * 2
+/// # #[cfg(not(feature = "alloca"))]
 /// assert_eq!(sum, 127 * 128);
 /// ```
 pub fn inplace_or_alloc_array<T, R, Consumer>(size: usize, consumer: Consumer) -> R
     where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
 {
-    match try_inplace_array(size, consumer) {
+    inplace_or_alloc_array_with_limit::<T, DEFAULT_STACK_LIMIT_BYTES, R, Consumer>(size, consumer)
+}
+
+/// `inplace_or_alloc_array_with_limit` works like `inplace_or_alloc_array`
+/// but places arrays that take no more than `LIMIT_BYTES` bytes.
+/// Larger arrays are allocated in the heap.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{
+///     inplace_or_alloc_array_with_limit,
+///     UninitializedSliceMemoryGuard,
+/// };
+///
+/// // 1000 items is too much for this tiny limit, so the vector of exactly 1000 items is allocated
+/// let len = inplace_or_alloc_array_with_limit::<u16, 1024, _, _>(1000, |guard| guard.len());
+/// assert_eq!(len, 1000);
+/// // But 100 items are placed on the stack and rounded up
+/// let len = inplace_or_alloc_array_with_limit::<u16, 1024, _, _>(100, |guard| guard.len());
+/// # #[cfg(not(feature = "alloca"))]
+/// assert_eq!(len, 128);
+/// ```
+pub fn inplace_or_alloc_array_with_limit<T, const LIMIT_BYTES: usize, R, Consumer>(size: usize, consumer: Consumer) -> R
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    inplace_or_alloc_array_with_size_classes::<T, DefaultSizeClasses, LIMIT_BYTES, R, Consumer>(size, consumer)
+}
+
+/// `inplace_or_alloc_array_with_size_classes` works like `inplace_or_alloc_array_with_limit`
+/// but chooses the length of placed array with `S` strategy instead of the default one.
+///
+/// See `SizeClasses` for the list of available strategies.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{
+///     inplace_or_alloc_array_with_size_classes,
+///     PowersOfTwo,
+///     DEFAULT_STACK_LIMIT_BYTES,
+/// };
+///
+/// let len = inplace_or_alloc_array_with_size_classes::<u16, PowersOfTwo, DEFAULT_STACK_LIMIT_BYTES, _, _>(
+///     1500,
+///     |guard| guard.len(),
+/// );
+/// assert_eq!(len, 2048);
+/// ```
+pub fn inplace_or_alloc_array_with_size_classes<T, S, const LIMIT_BYTES: usize, R, Consumer>(size: usize, consumer: Consumer) -> R
+    where S: SizeClasses,
+          Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R,
+{
+    match try_inplace_array_with_size_classes::<T, S, LIMIT_BYTES, R, Consumer>(size, consumer) {
         Ok(result) => result,
         Err(consumer) => alloc_array(size, consumer),
     }
 }
 
+/// `inplace_or_alloc_array_in` works like `inplace_or_alloc_array`
+/// but allocates too large arrays with given `allocator`.
+///
+/// See `ScratchAllocator` for an example.
+pub fn inplace_or_alloc_array_in<T, R, A, Consumer>(allocator: &A, size: usize, consumer: Consumer) -> R
+    where A: ScratchAllocator + ?Sized,
+          Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R,
+{
+    match try_inplace_array(size, consumer) {
+        Ok(result) => result,
+        Err(consumer) => alloc_array_in(allocator, size, consumer),
+    }
+}
+
+/// `inplace_or_try_alloc_array` works like `inplace_or_alloc_array`
+/// but does not panic or abort if the array cannot be allocated in the heap.
+/// `Err((error, consumer))` will be returned instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_try_alloc_array, AllocArrayError, UninitializedSliceMemoryGuard};
+///
+/// let result = inplace_or_try_alloc_array(128, |guard: UninitializedSliceMemoryGuard<u32>| guard.len());
+/// assert_eq!(result.ok(), Some(128));
+///
+/// let result = inplace_or_try_alloc_array(usize::MAX / 2, |guard: UninitializedSliceMemoryGuard<u32>| guard.len());
+/// assert!(matches!(result, Err((AllocArrayError::CapacityOverflow, _))));
+/// ```
+pub fn inplace_or_try_alloc_array<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, (AllocArrayError, Consumer)>
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    match try_inplace_array(size, consumer) {
+        Ok(result) => Ok(result),
+        Err(consumer) => try_alloc_array(size, consumer),
+    }
+}
+
+/// Default count of items `inplace_or_alloc_from_iter` places on the stack
+/// when iterator's upper bound is unknown or too large for the stack.
+pub const DEFAULT_FROM_ITER_CAPACITY: usize = 256;
+
 /// `inplace_or_alloc_from_iter` is helper function used to easy trying to place data from `Iterator`.
 ///
-/// It tries to get upper bound of `size_hint` of iterator and forward it to `inplace_or_alloc_array` function.
-/// It there is not upper bound hint from iterator, then it just `collect` your data into `Vec`.
+/// If upper bound of iterator's `size_hint` fits the stack, it's forwarded to `try_inplace_array` function.
+/// Otherwise, the array of lower bound or `DEFAULT_FROM_ITER_CAPACITY` items (whichever is greater)
+/// is placed on the stack, so filtered and unbounded iterators are placed on the stack too
+/// if they return just a few items.
+/// Use `inplace_or_alloc_from_iter_with_capacity` to choose another count.
+/// If even the lower bound does not fit the stack, items are collected into `Vec` of that capacity right away.
 ///
-/// If iterator contains more data that `size_hint` said
-/// (and more than `try_inplace_array` function placed on stack),
-/// then items will be moved and collected (by iterating) into `Vec` also.
+/// If iterator contains more data than placed on the stack,
+/// then items will be moved and collected (by iterating) into `Vec`.
 ///
 /// # Examples
 ///
@@ -88,23 +328,64 @@ pub fn inplace_or_alloc_array<T, R, Consumer>(size: usize, consumer: Consumer) -
 ///      format!("{}", mem.len())
 ///  });
 ///  assert_eq!(result, format!("{}", count));
+///
+/// // Only a few items of a million pass the filter, so they are placed on the stack
+/// let sum = ::inplace_it::inplace_or_alloc_from_iter((0..1_000_000).filter(|i| i % 300_000 == 0), |mem| {
+///     mem.iter().sum::<u32>()
+/// });
+/// assert_eq!(sum, 1_800_000);
 /// ```
 pub fn inplace_or_alloc_from_iter<Iter, R, Consumer>(iter: Iter, consumer: Consumer) -> R
     where Iter: Iterator,
           Consumer: FnOnce(&mut [Iter::Item]) -> R,
 {
-    match iter.size_hint().1 {
-        Some(upper_bound_hint) => {
-            inplace_or_alloc_array(upper_bound_hint, |uninitialized_guard| {
-                match uninitialized_guard.init_with_dyn_iter(iter) {
-                    Ok(mut guard) => consumer(&mut *guard),
-                    Err(mut vec) => consumer(&mut *vec),
-                }
-            })
+    inplace_or_alloc_from_iter_with_capacity(iter, DEFAULT_FROM_ITER_CAPACITY, consumer)
+}
+
+/// `inplace_or_alloc_from_iter_with_capacity` works like `inplace_or_alloc_from_iter`
+/// but places at least `capacity` items on the stack (as far as they fit the stack)
+/// when iterator's upper bound is unknown or too large for the stack.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_or_alloc_from_iter_with_capacity;
+///
+/// // Unbounded iterator which is expected to return about 1000 items
+/// let numbers = (1..).take_while(|i| i * i < 1_000_000);
+/// let len = inplace_or_alloc_from_iter_with_capacity(numbers, 1000, |mem: &mut [u32]| mem.len());
+/// assert_eq!(len, 999);
+/// ```
+pub fn inplace_or_alloc_from_iter_with_capacity<Iter, R, Consumer>(iter: Iter, capacity: usize, consumer: Consumer) -> R
+    where Iter: Iterator,
+          Consumer: FnOnce(&mut [Iter::Item]) -> R,
+{
+    let max_len = max_inplace_len::<Iter::Item>();
+    let len = match iter.size_hint() {
+        (_, Some(upper_bound_hint)) if upper_bound_hint <= max_len => upper_bound_hint,
+        (lower_bound_hint, _) if lower_bound_hint > max_len => {
+            let mut vec = Vec::with_capacity(lower_bound_hint);
+            vec.extend(iter);
+            return consumer(&mut vec);
         }
-        None => {
-            let mut vec = iter.collect::<Vec<_>>();
-            consumer(&mut *vec)
+        (lower_bound_hint, _) => lower_bound_hint.max(capacity).min(max_len),
+    };
+    let collect = |uninitialized_guard: UninitializedSliceMemoryGuard<Iter::Item>| {
+        match uninitialized_guard.init_with_dyn_iter(iter) {
+            Ok(mut guard) => consumer(&mut guard),
+            Err(mut vec) => consumer(&mut vec),
         }
+    };
+    match try_inplace_array(len, collect) {
+        Ok(result) => result,
+        // All items are collected into `Vec` if there is no memory
+        Err(collect) => collect(unsafe { UninitializedSliceMemoryGuard::new(&mut []) }),
     }
 }
+
+/// Largest length of array of `T` that `try_inplace_array` places with the default limit.
+#[inline]
+fn max_inplace_len<T>() -> usize {
+    let max_len = DEFAULT_STACK_LIMIT_BYTES.checked_div(size_of::<T>()).unwrap_or(usize::MAX);
+    max_default_class_size(max_len)
+}
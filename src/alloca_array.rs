@@ -0,0 +1,220 @@
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::size_classes::SizeClasses;
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+use crate::size_classes::Linear32;
+
+/// Exact size classes placed by moving the stack pointer (like C's `alloca`).
+///
+/// On x86_64 and aarch64 Linux placed array has exactly requested length and takes exactly
+/// `size * size_of::<T>()` bytes (plus alignment). Large arrays are placed page by page
+/// so the guard page of the stack is never skipped.
+/// On other targets it works just like `Linear32`.
+///
+/// With `alloca` feature enabled it's used by `try_inplace_array`, `inplace_or_alloc_array`
+/// and other functions which do not take `SizeClasses`.
+///
+/// Panics of the consumer unwind as usual, the stack pointer is restored on the way.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{
+///     inplace_or_alloc_array_with_size_classes,
+///     Alloca,
+///     UninitializedSliceMemoryGuard,
+///     DEFAULT_STACK_LIMIT_BYTES,
+/// };
+///
+/// let len = inplace_or_alloc_array_with_size_classes::<u16, Alloca, DEFAULT_STACK_LIMIT_BYTES, _, _>(
+///     100,
+///     |guard: UninitializedSliceMemoryGuard<u16>| guard.len(),
+/// );
+/// # #[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+/// assert_eq!(len, 100);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Alloca;
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+impl SizeClasses for Alloca {
+    #[inline]
+    fn class_len(size: usize) -> Option<usize> {
+        Some(size)
+    }
+
+    #[inline]
+    fn place<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        imp::place_alloca(size, consumer)
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+impl SizeClasses for Alloca {
+    #[inline]
+    fn class_len(size: usize) -> Option<usize> {
+        Linear32::class_len(size)
+    }
+
+    #[inline]
+    fn place<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        Linear32::place(size, consumer)
+    }
+
+    #[inline]
+    fn place_with_limit<T, const LIMIT_BYTES: usize, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        Linear32::place_with_limit::<T, LIMIT_BYTES, R, Consumer>(size, consumer)
+    }
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod imp {
+    use crate::guards::UninitializedSliceMemoryGuard;
+    use core::{
+        arch::naked_asm,
+        mem::{MaybeUninit, size_of, align_of},
+        slice::from_raw_parts_mut,
+    };
+
+    /// Both x86_64 and aarch64 require the stack pointer to be aligned to 16 bytes at calls.
+    const STACK_ALIGN: usize = 16;
+
+    /// Distance between stack probes. It should not be more than the size of the guard page.
+    const PROBE_STEP: usize = 4096;
+
+    type Callback = unsafe extern "C-unwind" fn(*mut u8, *mut u8);
+
+    struct State<R, Consumer> {
+        consumer: Option<Consumer>,
+        result: Option<R>,
+        len: usize,
+    }
+
+    /// Places an array of exactly `len` items of `T` below the current stack pointer
+    /// and pass the guard of memory into the `consumer` closure. `consumer`'s result will be returned as `Ok(result)`.
+    ///
+    /// If the array cannot be addressed below the current stack pointer then `Err(consumer)` will be returned.
+    pub(super) fn place_alloca<T, R, Consumer>(len: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        let align = if align_of::<T>() > STACK_ALIGN { align_of::<T>() } else { STACK_ALIGN };
+        let stack_pointer = &len as *const usize as usize;
+        let bytes = match len.checked_mul(size_of::<T>()) {
+            Some(bytes) if bytes.saturating_add(align) < stack_pointer => bytes,
+            _ => return Err(consumer),
+        };
+
+        let mut state = State {
+            consumer: Some(consumer),
+            result: None,
+            len,
+        };
+        unsafe {
+            alloca_raw(
+                &mut state as *mut State<R, Consumer> as *mut u8,
+                bytes,
+                align.wrapping_neg(),
+                trampoline::<T, R, Consumer>,
+            );
+        }
+        match state.result {
+            Some(result) => Ok(result),
+            None => unreachable!("Consumer is not called"),
+        }
+    }
+
+    unsafe extern "C-unwind" fn trampoline<T, R, Consumer>(state: *mut u8, memory: *mut u8)
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        let state = &mut *(state as *mut State<R, Consumer>);
+        if let Some(consumer) = state.consumer.take() {
+            let memory = from_raw_parts_mut(memory as *mut MaybeUninit<T>, state.len);
+            state.result = Some(consumer(UninitializedSliceMemoryGuard::new(memory)));
+        }
+    }
+
+    /// Moves the stack pointer down by `bytes` aligned with `align_mask`, touching every page on the way,
+    /// calls `callback(data, memory)` and restores the stack pointer.
+    ///
+    /// The frame is addressed with the frame pointer and described with CFI directives,
+    /// so panics of `callback` unwind through it.
+    #[cfg(target_arch = "x86_64")]
+    #[unsafe(naked)]
+    unsafe extern "C-unwind" fn alloca_raw(data: *mut u8, bytes: usize, align_mask: usize, callback: Callback) {
+        naked_asm!(
+            ".cfi_startproc",
+            "push rbp",
+            ".cfi_def_cfa_offset 16",
+            ".cfi_offset rbp, -16",
+            "mov rbp, rsp",
+            ".cfi_def_cfa_register rbp",
+            "mov rax, rsp",
+            "sub rax, rsi",
+            "and rax, rdx",
+            "2:",
+            "sub rsp, {step}",
+            "cmp rsp, rax",
+            "jbe 3f",
+            "test qword ptr [rsp], rsp",
+            "jmp 2b",
+            "3:",
+            "mov rsp, rax",
+            "test qword ptr [rsp], rsp",
+            "mov rsi, rsp",
+            "call rcx",
+            "mov rsp, rbp",
+            "pop rbp",
+            ".cfi_def_cfa rsp, 8",
+            "ret",
+            ".cfi_endproc",
+            step = const PROBE_STEP,
+        )
+    }
+
+    /// Moves the stack pointer down by `bytes` aligned with `align_mask`, touching every page on the way,
+    /// calls `callback(data, memory)` and restores the stack pointer.
+    ///
+    /// The frame is addressed with the frame pointer and described with CFI directives,
+    /// so panics of `callback` unwind through it.
+    #[cfg(target_arch = "aarch64")]
+    #[unsafe(naked)]
+    unsafe extern "C-unwind" fn alloca_raw(data: *mut u8, bytes: usize, align_mask: usize, callback: Callback) {
+        naked_asm!(
+            ".cfi_startproc",
+            "stp x29, x30, [sp, #-16]!",
+            ".cfi_def_cfa_offset 16",
+            ".cfi_offset w30, -8",
+            ".cfi_offset w29, -16",
+            "mov x29, sp",
+            ".cfi_def_cfa w29, 16",
+            "mov x9, sp",
+            "sub x9, x9, x1",
+            "and x9, x9, x2",
+            "2:",
+            "sub sp, sp, #{step}",
+            "cmp sp, x9",
+            "b.ls 3f",
+            "ldr xzr, [sp]",
+            "b 2b",
+            "3:",
+            "mov sp, x9",
+            "ldr xzr, [sp]",
+            "mov x1, sp",
+            "blr x3",
+            "mov sp, x29",
+            ".cfi_def_cfa wsp, 16",
+            "ldp x29, x30, [sp], #16",
+            ".cfi_def_cfa_offset 0",
+            ".cfi_restore w30",
+            ".cfi_restore w29",
+            "ret",
+            ".cfi_endproc",
+            step = const PROBE_STEP,
+        )
+    }
+}
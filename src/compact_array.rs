@@ -0,0 +1,152 @@
+use crate::guards::UninitializedSliceMemoryGuard;
+use core::{
+    mem::{MaybeUninit, size_of, align_of},
+    slice::from_raw_parts_mut,
+};
+
+/// Alignment of type-erased frames.
+/// Types with larger alignment are placed with padding.
+const FRAME_ALIGN: usize = 16;
+
+#[repr(C, align(16))]
+struct FrameChunk(#[allow(dead_code)] [u8; FRAME_ALIGN]);
+
+/// Places an array of `len` items of `T` in the type-erased frame and pass the guard of memory into the
+/// `consumer` closure. `consumer`'s result will be returned as `Ok(result)`.
+///
+/// Frames do not depend on `T`, `R` and `Consumer`, so they are shared among all callers.
+/// Only this small function is generated for each call site.
+///
+/// If there is no frame large enough then `Err(consumer)` will be returned.
+#[inline]
+pub(crate) fn place_compact<T, R, Consumer>(len: usize, consumer: Consumer) -> Result<R, Consumer>
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    let padding = align_of::<T>().saturating_sub(FRAME_ALIGN);
+    let bytes = match len.checked_mul(size_of::<T>()).and_then(|bytes| bytes.checked_add(padding)) {
+        Some(bytes) => bytes,
+        None => return Err(consumer),
+    };
+
+    let mut consumer = Some(consumer);
+    let mut result = None;
+    place_bytes(bytes, &mut |memory| {
+        if let Some(consumer) = consumer.take() {
+            unsafe {
+                let offset = memory.as_mut_ptr().align_offset(align_of::<T>());
+                let memory = from_raw_parts_mut(memory.as_mut_ptr().add(offset) as *mut MaybeUninit<T>, len);
+                result = Some(consumer(UninitializedSliceMemoryGuard::new(memory)));
+            }
+        }
+    });
+
+    match (result, consumer) {
+        (Some(result), _) => Ok(result),
+        (None, Some(consumer)) => Err(consumer),
+        (None, None) => unreachable!("Consumer is taken but result is not produced"),
+    }
+}
+
+/// Chooses a frame of at least `bytes` bytes and pass it into the `consumer`.
+/// Returns `false` if there is no frame large enough.
+#[inline(never)]
+fn place_bytes(bytes: usize, consumer: &mut dyn FnMut(&mut [MaybeUninit<u8>])) -> bool {
+    match bytes {
+        0..=64 => place_frame::<4>(consumer),
+        65..=96 => place_frame::<6>(consumer),
+        97..=128 => place_frame::<8>(consumer),
+        129..=192 => place_frame::<12>(consumer),
+        193..=256 => place_frame::<16>(consumer),
+        257..=384 => place_frame::<24>(consumer),
+        385..=512 => place_frame::<32>(consumer),
+        513..=768 => place_frame::<48>(consumer),
+        769..=1024 => place_frame::<64>(consumer),
+        1025..=1536 => place_frame::<96>(consumer),
+        1537..=2048 => place_frame::<128>(consumer),
+        2049..=3072 => place_frame::<192>(consumer),
+        3073..=4096 => place_frame::<256>(consumer),
+        4097..=6144 => place_frame::<384>(consumer),
+        6145..=8192 => place_frame::<512>(consumer),
+        8193..=12288 => place_frame::<768>(consumer),
+        12289..=16384 => place_frame::<1024>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        16385..=24576 => place_frame::<1536>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        24577..=32768 => place_frame::<2048>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        32769..=49152 => place_frame::<3072>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        49153..=65536 => place_frame::<4096>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        65537..=98304 => place_frame::<6144>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        98305..=131072 => place_frame::<8192>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        131073..=196608 => place_frame::<12288>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        196609..=262144 => place_frame::<16384>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        262145..=393216 => place_frame::<24576>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        393217..=524288 => place_frame::<32768>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        524289..=786432 => place_frame::<49152>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        786433..=1048576 => place_frame::<65536>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        1048577..=1572864 => place_frame::<98304>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        1572865..=2097152 => place_frame::<131072>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        2097153..=3145728 => place_frame::<196608>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        3145729..=4194304 => place_frame::<262144>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        4194305..=6291456 => place_frame::<393216>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        6291457..=8388608 => place_frame::<524288>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        8388609..=12582912 => place_frame::<786432>(consumer),
+        #[cfg(not(target_pointer_width = "16"))]
+        12582913..=16777216 => place_frame::<1048576>(consumer),
+        _ => return false,
+    }
+    true
+}
+
+#[inline(never)]
+fn place_frame<const CHUNKS: usize>(consumer: &mut dyn FnMut(&mut [MaybeUninit<u8>])) {
+    unsafe {
+        let mut memory: [MaybeUninit<FrameChunk>; CHUNKS] = MaybeUninit::uninit().assume_init();
+        consumer(from_raw_parts_mut(memory.as_mut_ptr() as *mut MaybeUninit<u8>, CHUNKS * FRAME_ALIGN))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(align(64))]
+    struct OverAligned(#[allow(dead_code)] u8);
+
+    #[test]
+    fn place_compact_aligns_memory() {
+        for len in [0, 1, 7, 100, 1000].iter().cloned() {
+            let placed = place_compact(len, |guard: UninitializedSliceMemoryGuard<OverAligned>| {
+                let guard = guard.init(|_| OverAligned(1));
+                assert_eq!(guard.as_ptr() as usize % align_of::<OverAligned>(), 0);
+                guard.len()
+            });
+            assert_eq!(placed.ok(), Some(len));
+
+            let placed = place_compact(len, |guard: UninitializedSliceMemoryGuard<()>| guard.len());
+            assert_eq!(placed.ok(), Some(len));
+        }
+    }
+
+    #[test]
+    fn place_compact_fails_without_large_enough_frame() {
+        let placed = place_compact(usize::MAX, |guard: UninitializedSliceMemoryGuard<u16>| guard.len());
+        assert!(placed.is_err());
+    }
+}
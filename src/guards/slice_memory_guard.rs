@@ -1,9 +1,12 @@
 use core::{
+    iter::FusedIterator,
     ops::{Deref, DerefMut},
-    mem::{MaybeUninit, transmute},
-    ptr::{drop_in_place, write, copy_nonoverlapping},
+    mem::{MaybeUninit, ManuallyDrop, take, transmute},
+    ptr::{drop_in_place, write, read, copy_nonoverlapping},
+    slice,
 };
 use alloc::vec::Vec;
+use crate::guards::UninitializedSliceMemoryGuard;
 
 /// Guard-struct used for correctly initialize uninitialized memory and `drop` it when guard goes out of scope.
 /// Usually, you *should not* use this struct to handle your memory.
@@ -44,12 +47,21 @@ pub struct SliceMemoryGuard<'a, T> {
 
 impl<'a, T> SliceMemoryGuard<'a, T> {
     /// Initialize memory guard
+    ///
+    /// If `init` panics, already initialized elements will be dropped.
+    ///
+    /// # Safety
+    ///
+    /// `memory`'s content will be overwritten without dropping, so it should not contain initialized data.
     #[inline]
     pub unsafe fn new(memory: &'a mut [MaybeUninit<T>], mut init: impl FnMut(usize) -> T) -> Self {
-        for (index, item) in memory.into_iter().enumerate() {
-            write(item.as_mut_ptr(), init(index));
+        // If `init` panics, already initialized items will be dropped by this guard
+        let mut initialization = Initialization::new(memory);
+        while initialization.initialized < initialization.memory.len() {
+            let value = init(initialization.initialized);
+            initialization.push(value);
         }
-        SliceMemoryGuard { memory }
+        SliceMemoryGuard { memory: initialization.finish() }
     }
 
     /// Initialize memory guard using given iterator.
@@ -58,21 +70,22 @@ impl<'a, T> SliceMemoryGuard<'a, T> {
     ///
     /// If items' count is too large to place in memory, moves it into new `Vec` and continue collecting into it.
     /// `Err(vec)` will be returned in this case.
+    ///
+    /// # Safety
+    ///
+    /// `memory`'s content will be overwritten without dropping, so it should not contain initialized data.
     #[inline]
     pub unsafe fn new_from_iter(memory: &'a mut [MaybeUninit<T>], mut iter: impl Iterator<Item=T>) -> Result<Self, Vec<T>> {
+        // If `iter` panics, already initialized items will be dropped by this guard
+        let mut initialization = Initialization::new(memory);
+
         // Fulfilling placed memory
-        for (index, item) in memory.into_iter().enumerate() {
+        while initialization.initialized < initialization.memory.len() {
             match iter.next() {
                 // While iterator returns new value, write it
-                Some(value) => {
-                    write(item.as_mut_ptr(), value);
-                }
+                Some(value) => initialization.push(value),
                 // When it returns None then slicing memory and returning the guard
-                None => {
-                    return Ok(SliceMemoryGuard {
-                        memory: &mut memory[0..index],
-                    });
-                }
+                None => return Ok(SliceMemoryGuard { memory: initialization.finish() }),
             }
         }
 
@@ -80,12 +93,13 @@ impl<'a, T> SliceMemoryGuard<'a, T> {
             // If iterator still contains values to return, collect it into the vector
             let mut vec = Vec::<T>::with_capacity(
                 // We cannot trust the `size_hint` anymore
-                memory.len() + 1
+                initialization.initialized + 1
             );
 
-            // First, copying already fulfilled memory into the heap
-            vec.set_len(memory.len());
+            // First, moving already fulfilled memory into the heap
+            let memory = initialization.finish();
             copy_nonoverlapping(memory.as_mut_ptr() as *mut T, vec.as_mut_ptr(), memory.len());
+            vec.set_len(memory.len());
 
             // Then, append it with the rest iterator's items
             vec.push(next_item);
@@ -94,7 +108,131 @@ impl<'a, T> SliceMemoryGuard<'a, T> {
             Err(vec)
         } else {
             // If iterator is done after fulfilling all available memory, just return the guard
-            Ok(SliceMemoryGuard { memory })
+            Ok(SliceMemoryGuard { memory: initialization.finish() })
+        }
+    }
+
+    /// Initialize memory guard with fallible `init` closure.
+    ///
+    /// If `init` returns an error, already initialized elements will be dropped
+    /// and `Err((error, memory))` will be returned. The same happens if `init` panics.
+    ///
+    /// # Safety
+    ///
+    /// `memory`'s content will be overwritten without dropping, so it should not contain initialized data.
+    #[inline]
+    pub unsafe fn try_new<E>(memory: &'a mut [MaybeUninit<T>], mut init: impl FnMut(usize) -> Result<T, E>) -> Result<Self, (E, &'a mut [MaybeUninit<T>])> {
+        let mut initialization = Initialization::new(memory);
+        while initialization.initialized < initialization.memory.len() {
+            match init(initialization.initialized) {
+                Ok(value) => initialization.push(value),
+                Err(error) => return Err((error, initialization.abort())),
+            }
+        }
+        Ok(SliceMemoryGuard { memory: initialization.finish() })
+    }
+
+    /// Make memory guard of already initialized memory.
+    ///
+    /// # Safety
+    ///
+    /// All elements of `memory` should be initialized.
+    #[inline]
+    pub(crate) unsafe fn from_initialized(memory: &'a mut [MaybeUninit<T>]) -> Self {
+        SliceMemoryGuard { memory }
+    }
+
+    /// Initialize memory guard using given iterator of fallible items.
+    /// Works like `new_from_iter` but stops on the first error.
+    ///
+    /// If iterator returns an error, already collected elements will be dropped
+    /// and `Err((error, memory))` will be returned.
+    ///
+    /// # Safety
+    ///
+    /// `memory`'s content will be overwritten without dropping, so it should not contain initialized data.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    pub unsafe fn try_new_from_iter<E>(memory: &'a mut [MaybeUninit<T>], mut iter: impl Iterator<Item=Result<T, E>>) -> Result<Result<Self, Vec<T>>, (E, &'a mut [MaybeUninit<T>])> {
+        let mut initialization = Initialization::new(memory);
+
+        while initialization.initialized < initialization.memory.len() {
+            match iter.next() {
+                Some(Ok(value)) => initialization.push(value),
+                Some(Err(error)) => return Err((error, initialization.abort())),
+                None => return Ok(Ok(SliceMemoryGuard { memory: initialization.finish() })),
+            }
+        }
+
+        let next_item = match iter.next() {
+            Some(Ok(value)) => value,
+            Some(Err(error)) => return Err((error, initialization.abort())),
+            None => return Ok(Ok(SliceMemoryGuard { memory: initialization.finish() })),
+        };
+
+        let mut vec = Vec::<T>::with_capacity(initialization.initialized + 1);
+        // Memory is fulfilled, so it's whole memory
+        let memory = initialization.finish();
+        copy_nonoverlapping(memory.as_mut_ptr() as *mut T, vec.as_mut_ptr(), memory.len());
+        vec.set_len(memory.len());
+        vec.push(next_item);
+
+        for item in iter {
+            match item {
+                Ok(value) => vec.push(value),
+                Err(error) => return Err((error, memory)),
+            }
+        }
+        Ok(Err(vec))
+    }
+}
+
+/// Tracks initialized prefix of memory and drops it if initialization is interrupted by panic.
+struct Initialization<'a, T> {
+    memory: &'a mut [MaybeUninit<T>],
+    initialized: usize,
+}
+
+impl<'a, T> Initialization<'a, T> {
+    #[inline]
+    fn new(memory: &'a mut [MaybeUninit<T>]) -> Self {
+        Self { memory, initialized: 0 }
+    }
+
+    /// Writes `value` after initialized prefix. Memory should not be fulfilled yet.
+    #[inline]
+    unsafe fn push(&mut self, value: T) {
+        write(self.memory.get_unchecked_mut(self.initialized).as_mut_ptr(), value);
+        self.initialized += 1;
+    }
+
+    /// Drops initialized prefix and returns whole memory.
+    #[inline]
+    fn abort(self) -> &'a mut [MaybeUninit<T>] {
+        let initialized = self.initialized;
+        let this = ManuallyDrop::new(self);
+        let memory = unsafe { read(&this.memory) };
+        unsafe {
+            drop_in_place(&mut memory[..initialized] as *mut [MaybeUninit<T>] as *mut [T]);
+        }
+        memory
+    }
+
+    /// Stops tracking and returns initialized prefix of memory.
+    #[inline]
+    fn finish(self) -> &'a mut [MaybeUninit<T>] {
+        let initialized = self.initialized;
+        let this = ManuallyDrop::new(self);
+        let memory = unsafe { read(&this.memory) };
+        &mut memory[..initialized]
+    }
+}
+
+impl<'a, T> Drop for Initialization<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            drop_in_place(&mut self.memory[..self.initialized] as *mut [MaybeUninit<T>] as *mut [T]);
         }
     }
 }
@@ -104,23 +242,315 @@ impl<'a, T> Deref for SliceMemoryGuard<'a, T> {
 
     #[inline]
     fn deref(&self) -> &Self::Target {
-        unsafe { transmute::<&[MaybeUninit<T>], &[T]>(&self.memory) }
+        unsafe { transmute::<&[MaybeUninit<T>], &[T]>(self.memory) }
     }
 }
 
 impl<'a, T> DerefMut for SliceMemoryGuard<'a, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { transmute::<&mut [MaybeUninit<T>], &mut [T]>(&mut self.memory) }
+        unsafe { transmute::<&mut [MaybeUninit<T>], &mut [T]>(self.memory) }
     }
 }
 
 impl<'a, T> Drop for SliceMemoryGuard<'a, T> {
     #[inline]
     fn drop(&mut self) {
-        for item in self.memory.into_iter() {
-            unsafe { drop_in_place(item.as_mut_ptr()); }
+        // Dropping the whole slice keeps dropping the rest items if one of them panics
+        unsafe { drop_in_place(&mut **self as *mut [T]); }
+    }
+}
+
+impl<'a, T> SliceMemoryGuard<'a, T> {
+    /// Drop all elements and return the guard of memory, so it can be initialized again.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// inplace_or_alloc_array(100, |mut guard: UninitializedSliceMemoryGuard<usize>| {
+    ///     for round in 0..3 {
+    ///         let initialized = guard.init(|index| index * round);
+    ///         assert_eq!(initialized[10], 10 * round);
+    ///         guard = initialized.clear();
+    ///     }
+    /// });
+    /// ```
+    #[inline]
+    pub fn clear(self) -> UninitializedSliceMemoryGuard<'a, T> {
+        let memory = self.into_memory();
+        unsafe {
+            drop_in_place(memory as *mut [MaybeUninit<T>] as *mut [T]);
+            UninitializedSliceMemoryGuard::new(memory)
+        }
+    }
+
+    /// Keep first `len` elements, drop the rest and return their memory as a separate guard.
+    /// Nothing is dropped if `len` is greater than or equal to the guard's length.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<u32>| {
+    ///     let guard = guard.slice(..10).init(|index| index as u32);
+    ///     let (head, tail) = guard.truncate(4);
+    ///     assert_eq!(&*head, &[0, 1, 2, 3]);
+    ///     let tail = tail.init(|index| 100 + index as u32);
+    ///     assert_eq!(&*tail, &[100, 101, 102, 103, 104, 105]);
+    /// });
+    /// ```
+    #[inline]
+    pub fn truncate(self, len: usize) -> (Self, UninitializedSliceMemoryGuard<'a, T>) {
+        let len = len.min(self.len());
+        let (head, tail) = self.into_memory().split_at_mut(len);
+        // Head is owned again before tail is dropped, so it's dropped even if tail's `drop` panics
+        let head = SliceMemoryGuard { memory: head };
+        unsafe {
+            drop_in_place(tail as *mut [MaybeUninit<T>] as *mut [T]);
+            (head, UninitializedSliceMemoryGuard::new(tail))
+        }
+    }
+
+    /// Split the guard into two guards at `mid` index.
+    /// The first one owns elements `[0, mid)`, the second one owns elements `[mid, len)`.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `mid > len`.
+    #[inline]
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        assert!(mid <= self.len(), "mid (is {}) should be <= len (is {})", mid, self.len());
+        let (head, tail) = self.into_memory().split_at_mut(mid);
+        (SliceMemoryGuard { memory: head }, SliceMemoryGuard { memory: tail })
+    }
+
+    /// Split the guard into guards of `chunk_len` elements.
+    ///
+    /// Like `slice::chunks_exact_mut`, the last `len % chunk_len` elements are not returned by the iterator.
+    /// They can be taken with `ChunksExact::into_remainder`.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `chunk_len` is 0.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<u32>| {
+    ///     let guard = guard.slice(..10).init(|index| index as u32);
+    ///     let mut chunks = guard.into_chunks_exact(4);
+    ///     let sums = chunks.by_ref().map(|chunk| chunk.iter().sum::<u32>()).collect::<Vec<_>>();
+    ///     assert_eq!(sums, vec![0 + 1 + 2 + 3, 4 + 5 + 6 + 7]);
+    ///     assert_eq!(&*chunks.into_remainder(), &[8, 9]);
+    /// });
+    /// ```
+    #[inline]
+    pub fn into_chunks_exact(self, chunk_len: usize) -> ChunksExact<'a, T> {
+        assert!(chunk_len != 0, "chunk_len should be greater than zero");
+        let chunked_len = self.len() - self.len() % chunk_len;
+        let (memory, remainder) = self.into_memory().split_at_mut(chunked_len);
+        ChunksExact { memory, remainder, chunk_len }
+    }
+
+    /// Stops owning elements and returns the whole memory.
+    #[inline]
+    fn into_memory(self) -> &'a mut [MaybeUninit<T>] {
+        let this = ManuallyDrop::new(self);
+        unsafe { read(&this.memory) }
+    }
+}
+
+impl<'a, T> IntoIterator for SliceMemoryGuard<'a, T> {
+    type Item = T;
+    type IntoIter = IntoIter<'a, T>;
+
+    /// Make iterator which moves elements out of the guard.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// inplace_or_alloc_array(3, |guard: UninitializedSliceMemoryGuard<String>| {
+    ///     let guard = guard.slice(..3).init(|index| index.to_string());
+    ///     let mut iter = guard.into_iter();
+    ///     let first: String = iter.next().unwrap();
+    ///     assert_eq!(first, "0");
+    ///     // The rest elements are dropped and memory can be used again
+    ///     let guard = iter.into_uninit();
+    ///     assert_eq!(guard.len(), 3);
+    /// });
+    /// ```
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        let end = self.len();
+        IntoIter { memory: self.into_memory(), start: 0, end }
+    }
+}
+
+impl<'b, 'a, T> IntoIterator for &'b SliceMemoryGuard<'a, T> {
+    type Item = &'b T;
+    type IntoIter = slice::Iter<'b, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'b, 'a, T> IntoIterator for &'b mut SliceMemoryGuard<'a, T> {
+    type Item = &'b mut T;
+    type IntoIter = slice::IterMut<'b, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator which moves elements out of `SliceMemoryGuard`.
+///
+/// Elements which are not returned by the iterator are dropped when it is dropped.
+/// Use `into_uninit` to drop them and get the memory back.
+pub struct IntoIter<'a, T> {
+    memory: &'a mut [MaybeUninit<T>],
+    start: usize,
+    end: usize,
+}
+
+impl<'a, T> IntoIter<'a, T> {
+    /// Get the rest elements as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { transmute::<&[MaybeUninit<T>], &[T]>(&self.memory[self.start..self.end]) }
+    }
+
+    /// Get the rest elements as a mutable slice.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { transmute::<&mut [MaybeUninit<T>], &mut [T]>(&mut self.memory[self.start..self.end]) }
+    }
+
+    /// Drop the rest elements and return the guard of memory, so it can be used again.
+    #[inline]
+    pub fn into_uninit(mut self) -> UninitializedSliceMemoryGuard<'a, T> {
+        self.drop_rest();
+        let this = ManuallyDrop::new(self);
+        unsafe { UninitializedSliceMemoryGuard::new(read(&this.memory)) }
+    }
+
+    #[inline]
+    fn drop_rest(&mut self) {
+        let rest = self.as_mut_slice() as *mut [T];
+        // If `drop` panics, the rest elements will not be dropped twice
+        self.start = self.end;
+        unsafe { drop_in_place(rest) }
+    }
+}
+
+impl<'a, T> Iterator for IntoIter<'a, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.start += 1;
+        Some(unsafe { read(self.memory.get_unchecked(self.start - 1).as_ptr()) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IntoIter<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        Some(unsafe { read(self.memory.get_unchecked(self.end).as_ptr()) })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IntoIter<'a, T> {}
+
+impl<'a, T> FusedIterator for IntoIter<'a, T> {}
+
+impl<'a, T> Drop for IntoIter<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.drop_rest();
+    }
+}
+
+/// Iterator of guards of `chunk_len` elements made by `SliceMemoryGuard::into_chunks_exact`.
+///
+/// Elements of chunks which are not returned by the iterator and elements of the remainder
+/// are dropped when it is dropped.
+pub struct ChunksExact<'a, T> {
+    memory: &'a mut [MaybeUninit<T>],
+    remainder: &'a mut [MaybeUninit<T>],
+    chunk_len: usize,
+}
+
+impl<'a, T> ChunksExact<'a, T> {
+    /// Drop elements of chunks which are not returned yet and return the guard of last `len % chunk_len` elements.
+    #[inline]
+    pub fn into_remainder(mut self) -> SliceMemoryGuard<'a, T> {
+        SliceMemoryGuard { memory: take(&mut self.remainder) }
+    }
+}
+
+impl<'a, T> Iterator for ChunksExact<'a, T> {
+    type Item = SliceMemoryGuard<'a, T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.memory.is_empty() {
+            return None;
         }
+        let (chunk, rest) = take(&mut self.memory).split_at_mut(self.chunk_len);
+        self.memory = rest;
+        Some(SliceMemoryGuard { memory: chunk })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.memory.len() / self.chunk_len;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for ChunksExact<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.memory.is_empty() {
+            return None;
+        }
+        let mid = self.memory.len() - self.chunk_len;
+        let (rest, chunk) = take(&mut self.memory).split_at_mut(mid);
+        self.memory = rest;
+        Some(SliceMemoryGuard { memory: chunk })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for ChunksExact<'a, T> {}
+
+impl<'a, T> FusedIterator for ChunksExact<'a, T> {}
+
+impl<'a, T> Drop for ChunksExact<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // Both parts are owned by guards, so the remainder is dropped even if chunk's `drop` panics
+        let _remainder = SliceMemoryGuard { memory: take(&mut self.remainder) };
+        let _chunks = SliceMemoryGuard { memory: take(&mut self.memory) };
     }
 }
 
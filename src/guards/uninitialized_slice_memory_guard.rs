@@ -1,5 +1,7 @@
 use core::{
-    mem::MaybeUninit,
+    iter::FusedIterator,
+    mem::{MaybeUninit, take},
+    ptr::write_bytes,
     ops::{
         RangeBounds,
         Bound,
@@ -68,6 +70,11 @@ pub struct UninitializedSliceMemoryGuard<'a, T> {
 
 impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
     /// Initialize memory guard
+    ///
+    /// # Safety
+    ///
+    /// `memory`'s content will be overwritten without dropping while initialization,
+    /// so it should not contain initialized data.
     #[inline]
     pub unsafe fn new(memory: &'a mut [MaybeUninit<T>]) -> Self {
         Self { memory }
@@ -79,6 +86,12 @@ impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
         self.memory.len()
     }
 
+    /// Check if memory slice is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
     /// Construct new memory guard with new bounds.
     ///
     /// Can be used to shrink memory.
@@ -103,8 +116,66 @@ impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
         }
     }
 
+    /// Split the guard into two guards at `mid` index.
+    /// The first one owns memory `[0, mid)`, the second one owns memory `[mid, len)`.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `mid > len`.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// // One placement for both input and output arrays
+    /// inplace_or_alloc_array(20, |guard: UninitializedSliceMemoryGuard<u32>| {
+    ///     let (input, output) = guard.slice(..20).split_at(10);
+    ///     let input = input.init(|index| index as u32);
+    ///     let output = output.init(|index| input[index] * 2);
+    ///     assert_eq!(output.iter().sum::<u32>(), 90);
+    /// });
+    /// ```
+    #[inline]
+    pub fn split_at(self, mid: usize) -> (Self, Self) {
+        let (head, tail) = self.memory.split_at_mut(mid);
+        (Self { memory: head }, Self { memory: tail })
+    }
+
+    /// Split the guard into guards of `chunk_len` items.
+    ///
+    /// Like `slice::chunks_exact_mut`, the last `len % chunk_len` items are not returned by the iterator.
+    /// They can be taken with `UninitializedChunksExact::into_remainder`.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `chunk_len` is 0.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// // Array per worker
+    /// inplace_or_alloc_array(100, |guard: UninitializedSliceMemoryGuard<u32>| {
+    ///     let sums = guard.slice(..100)
+    ///         .into_chunks_exact(25)
+    ///         .enumerate()
+    ///         .map(|(worker, chunk)| chunk.init(|index| (worker * 25 + index) as u32).iter().sum::<u32>())
+    ///         .collect::<Vec<_>>();
+    ///     assert_eq!(sums.iter().sum::<u32>(), 99 * 100 / 2);
+    /// });
+    /// ```
+    #[inline]
+    pub fn into_chunks_exact(self, chunk_len: usize) -> UninitializedChunksExact<'a, T> {
+        assert!(chunk_len != 0, "chunk_len should be greater than zero");
+        let chunked_len = self.memory.len() - self.memory.len() % chunk_len;
+        let (memory, remainder) = self.memory.split_at_mut(chunked_len);
+        UninitializedChunksExact { memory, remainder, chunk_len }
+    }
+
     /// Initialize memory and make new guard of initialized memory.
     /// Given `init` closure will be used to initialize elements of memory slice.
+    ///
+    /// If `init` panics, already initialized elements will be dropped.
     #[inline]
     pub fn init(self, init: impl FnMut(usize) -> T) -> SliceMemoryGuard<'a, T> {
         unsafe {
@@ -112,6 +183,33 @@ impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
         }
     }
 
+    /// Initialize memory and make new guard of initialized memory.
+    /// Given fallible `init` closure will be used to initialize elements of memory slice.
+    ///
+    /// On the first error already initialized elements will be dropped
+    /// and `Err((error, self))` will be returned, so memory can be used again.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// inplace_or_alloc_array(3, |guard: UninitializedSliceMemoryGuard<u32>| {
+    ///     let source = ["1", "2", "x"];
+    ///     let guard = guard.slice(..3);
+    ///     let (error, guard) = guard.try_init(|index| source[index].parse::<u32>()).err().unwrap();
+    ///     assert_eq!(error.to_string(), "invalid digit found in string");
+    ///     let guard = guard.try_init(|index| source[index].replace('x', "3").parse::<u32>()).ok().unwrap();
+    ///     assert_eq!(&*guard, &[1, 2, 3]);
+    /// });
+    /// ```
+    #[inline]
+    pub fn try_init<E>(self, init: impl FnMut(usize) -> Result<T, E>) -> Result<SliceMemoryGuard<'a, T>, (E, Self)> {
+        unsafe {
+            SliceMemoryGuard::try_new(self.memory, init)
+                .map_err(|(error, memory)| (error, UninitializedSliceMemoryGuard::new(memory)))
+        }
+    }
+
     /// Initialize memory and make new guard of initialized memory.
     /// Given `source` slice will be used to initialize elements of memory slice.
     /// Returned guard will contain sliced memory to `source`'s length.
@@ -144,6 +242,8 @@ impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
     ///
     /// If items' count is too large to place in memory, moves it into new `Vec` and continue collecting into it.
     /// `Err(vec)` will be returned in this case.
+    ///
+    /// If `iter` panics, already collected elements will be dropped.
     #[inline]
     pub fn init_with_dyn_iter(self, iter: impl Iterator<Item = T>) -> Result<SliceMemoryGuard<'a, T>, Vec<T>> {
         unsafe {
@@ -151,12 +251,140 @@ impl<'a, T> UninitializedSliceMemoryGuard<'a, T> {
         }
     }
 
+    /// Initialize memory guard using given iterator of fallible items.
+    /// Works like `init_with_dyn_iter` but stops on the first error.
+    ///
+    /// On the first error already collected elements will be dropped
+    /// and `Err((error, self))` will be returned, so memory can be used again.
+    #[inline]
+    pub fn try_init_with_dyn_iter<E>(self, iter: impl Iterator<Item = Result<T, E>>) -> Result<Result<SliceMemoryGuard<'a, T>, Vec<T>>, (E, Self)> {
+        unsafe {
+            SliceMemoryGuard::try_new_from_iter(self.memory, iter)
+                .map_err(|(error, memory)| (error, UninitializedSliceMemoryGuard::new(memory)))
+        }
+    }
+
+    /// Initialize first elements of memory using raw `fill` closure.
+    /// `fill` takes pointer to the memory and its length and returns count of elements it initialized.
+    ///
+    /// Returns guard of initialized elements and guard of the rest uninitialized memory.
+    /// It's useful for `read(2)`-like APIs which fill a buffer and return count of written elements.
+    ///
+    /// ### Safety
+    ///
+    /// `fill` should initialize exactly the first elements of memory and return their count.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when returned count is greater than memory's length.
+    /// Initialized elements will be leaked in this case.
+    #[inline]
+    pub unsafe fn init_with_raw(self, fill: impl FnOnce(*mut T, usize) -> usize) -> (SliceMemoryGuard<'a, T>, Self) {
+        let len = self.memory.len();
+        let count = fill(self.memory.as_mut_ptr() as *mut T, len);
+        assert!(count <= len, "initialized count {} is greater than memory length {}", count, len);
+        let (initialized, rest) = self.memory.split_at_mut(count);
+        (SliceMemoryGuard::from_initialized(initialized), UninitializedSliceMemoryGuard::new(rest))
+    }
+
+    /// Get memory slice owned by this guard.
+    #[inline]
+    pub(crate) fn into_memory(self) -> &'a mut [MaybeUninit<T>] {
+        self.memory
+    }
+
     /// Create new uninit memory guard with less or equal lifetime to original guard's lifetime.
     /// This function should be used to reuse memory because init-API consumes the guard.
     #[inline]
-    pub fn borrow(&mut self) -> UninitializedSliceMemoryGuard<T> {
+    pub fn borrow(&mut self) -> UninitializedSliceMemoryGuard<'_, T> {
         unsafe {
             UninitializedSliceMemoryGuard::new(self.memory)
         }
     }
 }
+
+/// Iterator of guards of `chunk_len` items made by `UninitializedSliceMemoryGuard::into_chunks_exact`.
+pub struct UninitializedChunksExact<'a, T> {
+    memory: &'a mut [MaybeUninit<T>],
+    remainder: &'a mut [MaybeUninit<T>],
+    chunk_len: usize,
+}
+
+impl<'a, T> UninitializedChunksExact<'a, T> {
+    /// Get the guard of last `len % chunk_len` items.
+    #[inline]
+    pub fn into_remainder(self) -> UninitializedSliceMemoryGuard<'a, T> {
+        UninitializedSliceMemoryGuard { memory: self.remainder }
+    }
+}
+
+impl<'a, T> Iterator for UninitializedChunksExact<'a, T> {
+    type Item = UninitializedSliceMemoryGuard<'a, T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.memory.is_empty() {
+            return None;
+        }
+        let (chunk, rest) = take(&mut self.memory).split_at_mut(self.chunk_len);
+        self.memory = rest;
+        Some(UninitializedSliceMemoryGuard { memory: chunk })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.memory.len() / self.chunk_len;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for UninitializedChunksExact<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.memory.is_empty() {
+            return None;
+        }
+        let mid = self.memory.len() - self.chunk_len;
+        let (rest, chunk) = take(&mut self.memory).split_at_mut(mid);
+        self.memory = rest;
+        Some(UninitializedSliceMemoryGuard { memory: chunk })
+    }
+}
+
+impl<'a, T> ExactSizeIterator for UninitializedChunksExact<'a, T> {}
+
+impl<'a, T> FusedIterator for UninitializedChunksExact<'a, T> {}
+
+impl<'a> UninitializedSliceMemoryGuard<'a, u8> {
+    /// Initialize first bytes of memory using `fill` closure.
+    /// `fill` takes zeroed memory and returns count of bytes it has written.
+    ///
+    /// Safe version of `init_with_raw` for byte buffers.
+    /// Returns guard of written bytes and guard of the rest uninitialized memory.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when returned count is greater than memory's length.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use inplace_it::{inplace_or_alloc_array, UninitializedSliceMemoryGuard};
+    ///
+    /// let source = b"hello, world";
+    /// inplace_or_alloc_array(64, |guard: UninitializedSliceMemoryGuard<u8>| {
+    ///     let (read, rest) = guard.init_with_bytes(|buffer| {
+    ///         buffer[..source.len()].copy_from_slice(source);
+    ///         source.len()
+    ///     });
+    ///     assert_eq!(&*read, b"hello, world");
+    ///     assert_eq!(rest.len(), 64 - source.len());
+    /// });
+    /// ```
+    #[inline]
+    pub fn init_with_bytes(self, fill: impl FnOnce(&mut [u8]) -> usize) -> (SliceMemoryGuard<'a, u8>, Self) {
+        unsafe {
+            write_bytes(self.memory.as_mut_ptr(), 0, self.memory.len());
+            self.init_with_raw(|ptr, len| fill(core::slice::from_raw_parts_mut(ptr, len)))
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use core::{
+    mem::{MaybeUninit, align_of, size_of},
+    slice::from_raw_parts_mut,
+};
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::alloc_array::inplace_or_alloc_array;
+
+/// `inplace_or_alloc_arrays` places several arrays of different types at once and pass the tuple
+/// of their guards into the `consumer` closure. `consumer`'s result will be returned.
+///
+/// Sizes of arrays are passed as a tuple of the same length. Tuples of up to 8 arrays are supported.
+///
+/// All arrays are placed in one array on the stack, so they are limited with one stack limit and budget
+/// as if they were one array (see `inplace_or_alloc_array`).
+/// If they take too much memory, they are allocated in the heap with one allocation.
+///
+/// Unlike `inplace_or_alloc_array`, lengths of arrays are always equal to requested.
+///
+/// ### Panics
+///
+/// Panic can be reached when arrays take more than `isize::MAX` bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_arrays, UninitializedSliceMemoryGuard};
+///
+/// let values = [0.5, 1.5, 2.5, 3.5];
+/// let sum = inplace_or_alloc_arrays(
+///     (values.len(), values.len()),
+///     |(indices, scaled): (UninitializedSliceMemoryGuard<u32>, UninitializedSliceMemoryGuard<f64>)| {
+///         let indices = indices.init(|index| (values.len() - index - 1) as u32);
+///         let scaled = scaled.init(|index| values[indices[index] as usize] * 2.0);
+///         scaled.iter().sum::<f64>()
+///     },
+/// );
+/// assert_eq!(sum, 16.0);
+/// ```
+pub fn inplace_or_alloc_arrays<Types, R, Consumer>(sizes: Consumer::Sizes, consumer: Consumer) -> R
+    where Consumer: ArraysConsumer<Types, R>,
+{
+    let (bytes, align) = Consumer::layout(&sizes).expect("capacity overflow");
+    // Arrays aligned more than chunks need padding before them
+    let chunks = bytes
+        .checked_add(align.saturating_sub(align_of::<ArrayChunk>()))
+        .and_then(|bytes| bytes.checked_add(size_of::<ArrayChunk>() - 1))
+        .expect("capacity overflow")
+        / size_of::<ArrayChunk>();
+    inplace_or_alloc_array(chunks, |guard: UninitializedSliceMemoryGuard<ArrayChunk>| {
+        let memory = guard.into_memory();
+        let ptr = memory.as_mut_ptr() as *mut u8;
+        let padding = (ptr as usize).wrapping_neg() & (align - 1);
+        unsafe { consumer.consume(sizes, ptr.add(padding)) }
+    })
+}
+
+/// Piece of memory arrays are placed in. It's aligned enough for most types.
+#[repr(C, align(16))]
+struct ArrayChunk(#[allow(dead_code)] [u8; 16]);
+
+/// Closure which takes a tuple of `UninitializedSliceMemoryGuard`s of `Types` (a tuple of item types).
+/// Used by `inplace_or_alloc_arrays`.
+///
+/// It's implemented for closures taking tuples of up to 8 guards and cannot be implemented outside of this crate.
+pub trait ArraysConsumer<Types, R>: sealed::Sealed<Types, R> {
+    /// Tuple of arrays' lengths.
+    type Sizes;
+
+    /// Get count of bytes and alignment of memory taken by arrays of given `sizes`.
+    /// `None` will be returned if count of bytes overflows.
+    fn layout(sizes: &Self::Sizes) -> Option<(usize, usize)>;
+
+    /// Make guards of arrays placed in memory at `base` and pass them into the closure.
+    ///
+    /// # Safety
+    ///
+    /// `base` should point to unused memory of layout returned by `layout`.
+    unsafe fn consume(self, sizes: Self::Sizes, base: *mut u8) -> R;
+}
+
+mod sealed {
+    /// Keeps `ArraysConsumer` implemented only by this crate, so `layout` always covers arrays made by `consume`.
+    pub trait Sealed<Types, R> {}
+}
+
+/// Finds place for array of `len` items of `T` after `end` bytes.
+/// Moves `end` after the array and returns its offset.
+#[inline]
+fn next_array<T>(end: &mut usize, len: usize) -> Option<usize> {
+    let offset = end.checked_add(align_of::<T>() - 1)? & !(align_of::<T>() - 1);
+    *end = offset.checked_add(size_of::<T>().checked_mul(len)?)?;
+    Some(offset)
+}
+
+macro_rules! arrays_consumer {
+    ($($T:ident $len:ident),+) => {
+        impl<$($T,)+ R, F> sealed::Sealed<($($T,)+), R> for F
+            where F: FnOnce(($(UninitializedSliceMemoryGuard<$T>,)+)) -> R
+        {}
+
+        impl<$($T,)+ R, F> ArraysConsumer<($($T,)+), R> for F
+            where F: FnOnce(($(UninitializedSliceMemoryGuard<$T>,)+)) -> R
+        {
+            type Sizes = ($(arrays_consumer!(@size $T),)+);
+
+            #[inline]
+            fn layout(sizes: &Self::Sizes) -> Option<(usize, usize)> {
+                let ($($len,)+) = *sizes;
+                let mut end = 0;
+                $(next_array::<$T>(&mut end, $len)?;)+
+                let align = 1 $(.max(align_of::<$T>()))+;
+                Some((end, align))
+            }
+
+            #[inline]
+            unsafe fn consume(self, sizes: Self::Sizes, base: *mut u8) -> R {
+                let ($($len,)+) = sizes;
+                let mut end = 0;
+                self(($(
+                    {
+                        // Offsets do not overflow since `layout` is checked
+                        let offset = next_array::<$T>(&mut end, $len).unwrap_or(0);
+                        UninitializedSliceMemoryGuard::new(from_raw_parts_mut(base.add(offset) as *mut MaybeUninit<$T>, $len))
+                    },
+                )+))
+            }
+        }
+    };
+    (@size $T:ident) => { usize };
+}
+
+arrays_consumer!(T1 len1);
+arrays_consumer!(T1 len1, T2 len2);
+arrays_consumer!(T1 len1, T2 len2, T3 len3);
+arrays_consumer!(T1 len1, T2 len2, T3 len3, T4 len4);
+arrays_consumer!(T1 len1, T2 len2, T3 len3, T4 len4, T5 len5);
+arrays_consumer!(T1 len1, T2 len2, T3 len3, T4 len4, T5 len5, T6 len6);
+arrays_consumer!(T1 len1, T2 len2, T3 len3, T4 len4, T5 len5, T6 len6, T7 len7);
+arrays_consumer!(T1 len1, T2 len2, T3 len3, T4 len4, T5 len5, T6 len6, T7 len7, T8 len8);
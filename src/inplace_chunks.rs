@@ -0,0 +1,103 @@
+use core::ops::ControlFlow;
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::fixed_array::try_inplace_array;
+
+/// `inplace_chunks` passes items of `iter` into the `consumer` closure by batches of `chunk_len` items.
+/// The last batch may be shorter.
+///
+/// Just one array of `chunk_len` items is placed on the stack (with `try_inplace_array`)
+/// and filled again for each batch. Items of each batch are dropped before the next batch is collected.
+/// So iterators of any length are processed without touching the heap.
+///
+/// If the array of `chunk_len` items cannot be placed on the stack,
+/// then untouched `Err((iter, consumer))` will be returned.
+///
+/// ### Panics
+///
+/// Panic can be reached when `chunk_len` is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_chunks;
+///
+/// let mut sums = Vec::new();
+/// inplace_chunks(1..=10, 4, |batch: &mut [u32]| sums.push(batch.iter().sum::<u32>()))
+///     .map_err(|_| "Cannot place the batch")
+///     .unwrap();
+/// assert_eq!(sums, vec![1 + 2 + 3 + 4, 5 + 6 + 7 + 8, 9 + 10]);
+/// ```
+pub fn inplace_chunks<Iter, Consumer>(mut iter: Iter, chunk_len: usize, mut consumer: Consumer) -> Result<(), (Iter, Consumer)>
+    where Iter: Iterator,
+          Consumer: FnMut(&mut [Iter::Item]),
+{
+    let mut consumer_with_flow = |batch: &mut [Iter::Item]| {
+        consumer(batch);
+        ControlFlow::<()>::Continue(())
+    };
+    match for_each_chunk(&mut iter, chunk_len, &mut consumer_with_flow) {
+        Some(_) => Ok(()),
+        None => Err((iter, consumer)),
+    }
+}
+
+/// `inplace_chunks_while` works like `inplace_chunks`
+/// but stops processing batches when `consumer` returns `ControlFlow::Break`.
+///
+/// `Ok(ControlFlow::Break(value))` will be returned in this case.
+/// The rest items of the batch are dropped and the rest items of `iter` are not touched.
+///
+/// ### Panics
+///
+/// Panic can be reached when `chunk_len` is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_chunks_while;
+/// use core::ops::ControlFlow;
+///
+/// // Find the first batch which sum is greater than 20
+/// let result = inplace_chunks_while(1.., 3, |batch: &mut [u32]| {
+///     let sum = batch.iter().sum::<u32>();
+///     if sum > 20 { ControlFlow::Break(sum) } else { ControlFlow::Continue(()) }
+/// });
+/// assert_eq!(result.ok(), Some(ControlFlow::Break(7 + 8 + 9)));
+/// ```
+pub fn inplace_chunks_while<Iter, B, Consumer>(mut iter: Iter, chunk_len: usize, mut consumer: Consumer) -> Result<ControlFlow<B>, (Iter, Consumer)>
+    where Iter: Iterator,
+          Consumer: FnMut(&mut [Iter::Item]) -> ControlFlow<B>,
+{
+    match for_each_chunk(&mut iter, chunk_len, &mut consumer) {
+        Some(flow) => Ok(flow),
+        None => Err((iter, consumer)),
+    }
+}
+
+/// Places the batch and passes items of `iter` through it.
+/// `None` will be returned if the batch cannot be placed.
+fn for_each_chunk<Iter, B, Consumer>(iter: &mut Iter, chunk_len: usize, consumer: &mut Consumer) -> Option<ControlFlow<B>>
+    where Iter: Iterator,
+          Consumer: FnMut(&mut [Iter::Item]) -> ControlFlow<B>,
+{
+    assert!(chunk_len != 0, "chunk_len should be greater than zero");
+    try_inplace_array(chunk_len, |guard: UninitializedSliceMemoryGuard<Iter::Item>| {
+        let mut guard = guard.slice(..chunk_len);
+        loop {
+            let mut batch = match guard.borrow().init_with_dyn_iter(iter.by_ref().take(chunk_len)) {
+                Ok(batch) => batch,
+                Err(_) => unreachable!("`take` never returns more items than memory holds"),
+            };
+            if batch.is_empty() {
+                return ControlFlow::Continue(());
+            }
+            let is_last = batch.len() < chunk_len;
+            if let ControlFlow::Break(value) = consumer(&mut batch) {
+                return ControlFlow::Break(value);
+            }
+            if is_last {
+                return ControlFlow::Continue(());
+            }
+        }
+    }).ok()
+}
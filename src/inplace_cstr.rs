@@ -0,0 +1,90 @@
+use core::{
+    ffi::CStr,
+    fmt,
+    ptr::copy_nonoverlapping,
+};
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::alloc_array::inplace_or_alloc_array;
+
+/// Error returned by `inplace_cstr` if given bytes contain a nul byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteriorNulError {
+    position: usize,
+}
+
+impl InteriorNulError {
+    /// Get position of the first nul byte.
+    #[inline]
+    pub fn nul_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for InteriorNulError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nul byte found in provided data at position: {}", self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InteriorNulError {}
+
+/// `inplace_cstr` copies `bytes` and terminating nul byte into a buffer placed with `inplace_or_alloc_array`
+/// and pass it as `CStr` into the `consumer` closure.
+/// `consumer`'s result will be returned as `Ok(result)`.
+///
+/// If `bytes` contain a nul byte, `Err(InteriorNulError)` will be returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_cstr;
+/// use core::ffi::CStr;
+///
+/// let len = inplace_cstr("hello", |string: &CStr| string.to_bytes_with_nul().len());
+/// assert_eq!(len, Ok(6));
+///
+/// let error = inplace_cstr(b"hel\0lo", |_: &CStr| ()).unwrap_err();
+/// assert_eq!(error.nul_position(), 3);
+/// ```
+pub fn inplace_cstr<R, Consumer>(bytes: impl AsRef<[u8]>, consumer: Consumer) -> Result<R, InteriorNulError>
+    where Consumer: FnOnce(&CStr) -> R
+{
+    let bytes = bytes.as_ref();
+    if let Some(position) = bytes.iter().position(|byte| *byte == 0) {
+        return Err(InteriorNulError { position });
+    }
+    Ok(inplace_or_alloc_array(bytes.len() + 1, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let (string, _) = unsafe {
+            guard.init_with_raw(|ptr, _| {
+                copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+                ptr.add(bytes.len()).write(0);
+                bytes.len() + 1
+            })
+        };
+        // There is just one nul byte and it's the last one
+        consumer(unsafe { CStr::from_bytes_with_nul_unchecked(&string) })
+    }))
+}
+
+/// `inplace_os_cstr` works like `inplace_cstr` but takes `OsStr` or `Path`.
+///
+/// Bytes of `OsStr::as_encoded_bytes` are used, so on Unix they are the same bytes which are passed into syscalls.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_os_cstr;
+/// use std::{ffi::CStr, path::Path};
+///
+/// let result = inplace_os_cstr(Path::new("/tmp/file.txt"), |path: &CStr| path.to_bytes().len());
+/// assert_eq!(result, Ok(13));
+/// ```
+#[cfg(feature = "std")]
+pub fn inplace_os_cstr<R, Consumer>(string: impl AsRef<OsStr>, consumer: Consumer) -> Result<R, InteriorNulError>
+    where Consumer: FnOnce(&CStr) -> R
+{
+    inplace_cstr(string.as_ref().as_encoded_bytes(), consumer)
+}
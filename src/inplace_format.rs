@@ -0,0 +1,132 @@
+use core::{
+    fmt::{self, Arguments, Write},
+    ptr::copy_nonoverlapping,
+    slice::from_raw_parts,
+    str::from_utf8_unchecked,
+};
+use alloc::string::String;
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::fixed_array::try_inplace_array;
+use crate::alloc_array::inplace_or_alloc_array;
+
+/// Length of the buffer which is placed first. Most of short strings fit it.
+const INITIAL_FORMAT_LEN: usize = 128;
+
+/// `inplace_format` formats `args` into a buffer placed on the stack and pass the string into the `consumer` closure.
+/// `consumer`'s result will be returned.
+///
+/// Short strings are formatted into the buffer of 128 bytes.
+/// If the string is longer, it is formatted again into the buffer of its length
+/// (rounded up like in `try_inplace_array`), or into the buffer allocated with `alloc_array`
+/// if the string is too long for the stack.
+/// So `consumer` always takes the complete string.
+///
+/// Formatting trait implementations of `args` run at most twice.
+/// If they return a longer string the second time, it's moved into `String` while being formatted.
+///
+/// ### Panics
+///
+/// Panic can be reached when a formatting trait implementation returns an error.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_format;
+///
+/// let (user, id) = ("alice", 42);
+/// let len = inplace_format(format_args!("user:{}:{}", user, id), |key: &str| {
+///     assert_eq!(key, "user:alice:42");
+///     key.len()
+/// });
+/// assert_eq!(len, 13);
+///
+/// // Long strings are complete too
+/// let len = inplace_format(format_args!("{:>1000}", "right"), |text: &str| text.len());
+/// assert_eq!(len, 1000);
+/// ```
+pub fn inplace_format<R, Consumer>(args: Arguments<'_>, consumer: Consumer) -> R
+    where Consumer: FnOnce(&str) -> R
+{
+    // Strings without arguments need no formatting
+    if let Some(string) = args.as_str() {
+        return consumer(string);
+    }
+    // Consumer is taken only when the string is complete
+    let mut consumer = Some(consumer);
+    let required = match try_inplace_array(INITIAL_FORMAT_LEN, |guard| format_into(guard, args, false, &mut consumer)) {
+        Ok(Ok(result)) => return result,
+        Ok(Err(required)) => required,
+        Err(_) => INITIAL_FORMAT_LEN,
+    };
+    // Formatting trait implementations may return longer string the next time, so it may be spilled
+    match inplace_or_alloc_array(required, |guard| format_into(guard, args, true, &mut consumer)) {
+        Ok(result) => result,
+        Err(_) => unreachable!("Spilled string is always complete"),
+    }
+}
+
+/// Formats `args` into memory of `guard` and pass the string into the `consumer`.
+/// If memory is not enough, the string is moved into `String` when `spill` is `true`
+/// or `Err(required_len)` is returned otherwise.
+fn format_into<R, Consumer>(guard: UninitializedSliceMemoryGuard<u8>, args: Arguments<'_>, spill: bool, consumer: &mut Option<Consumer>) -> Result<R, usize>
+    where Consumer: FnOnce(&str) -> R
+{
+    let mut required = 0;
+    let mut spilled = None;
+    let (written, _) = unsafe {
+        guard.init_with_raw(|ptr, capacity| {
+            let mut buffer = FormatBuffer { ptr, capacity, written: 0, len: 0, spill, spilled: None };
+            if fmt::write(&mut buffer, args).is_err() {
+                panic!("a formatting trait implementation returned an error");
+            }
+            required = buffer.len;
+            spilled = buffer.spilled;
+            buffer.written
+        })
+    };
+    if let Some(string) = spilled {
+        return Ok(take_consumer(consumer)(&string));
+    }
+    if required > written.len() {
+        return Err(required);
+    }
+    // Buffer contains complete concatenation of strings
+    Ok(take_consumer(consumer)(unsafe { from_utf8_unchecked(&written) }))
+}
+
+#[inline]
+fn take_consumer<Consumer>(consumer: &mut Option<Consumer>) -> Consumer {
+    consumer.take().expect("consumer is taken once")
+}
+
+/// Writes strings into memory while they fit it. Counts length of all strings.
+/// If `spill` is `true`, strings which do not fit are written into `spilled` after the written ones.
+struct FormatBuffer {
+    ptr: *mut u8,
+    capacity: usize,
+    written: usize,
+    len: usize,
+    spill: bool,
+    spilled: Option<String>,
+}
+
+impl Write for FormatBuffer {
+    #[inline]
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        // Once a string does not fit, nothing is written into memory anymore
+        if let Some(spilled) = &mut self.spilled {
+            spilled.push_str(string);
+        } else if self.written == self.len && string.len() <= self.capacity - self.written {
+            unsafe { copy_nonoverlapping(string.as_ptr(), self.ptr.add(self.written), string.len()) };
+            self.written += string.len();
+        } else if self.spill {
+            let mut spilled = String::with_capacity(self.written.saturating_add(string.len()));
+            // Written bytes are concatenation of strings
+            spilled.push_str(unsafe { from_utf8_unchecked(from_raw_parts(self.ptr, self.written)) });
+            spilled.push_str(string);
+            self.spilled = Some(spilled);
+        }
+        self.len = self.len.saturating_add(string.len());
+        Ok(())
+    }
+}
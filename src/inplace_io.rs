@@ -0,0 +1,304 @@
+use core::{
+    ptr::write_bytes,
+    slice::from_raw_parts_mut,
+};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    vec::Vec,
+};
+use crate::guards::{SliceMemoryGuard, UninitializedSliceMemoryGuard};
+use crate::alloc_array::inplace_or_alloc_array;
+
+/// Length of the buffer `read_to_end_inplace` reads into before moving bytes into the heap.
+pub const DEFAULT_IO_BUFFER_LEN: usize = 8 * 1024;
+
+/// Length of the buffer used to check that a reader has no more bytes.
+const PROBE_LEN: usize = 32;
+
+/// Length of the first zeroed chunk of I/O buffers.
+const MIN_ZEROED_LEN: usize = 512;
+
+/// `read_exact_inplace` reads exactly `len` bytes from `reader` into a buffer placed with
+/// `inplace_or_alloc_array` and pass them into the `consumer` closure.
+/// `consumer`'s result will be returned as `Ok(result)`.
+///
+/// Errors of `Read::read_exact` are returned as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::read_exact_inplace;
+///
+/// let mut reader: &[u8] = b"\x00\x05hello world";
+/// let len = read_exact_inplace(&mut reader, 2, |header: &[u8]| u16::from_be_bytes([header[0], header[1]])).unwrap();
+/// let body = read_exact_inplace(&mut reader, len as usize, |body: &[u8]| body.to_vec()).unwrap();
+/// assert_eq!(body, b"hello");
+/// ```
+pub fn read_exact_inplace<Reader, R, Consumer>(mut reader: Reader, len: usize, consumer: Consumer) -> io::Result<R>
+    where Reader: Read,
+          Consumer: FnOnce(&[u8]) -> R,
+{
+    inplace_or_alloc_array(len, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let mut result = Ok(());
+        let (bytes, _) = guard.slice(..len).init_with_bytes(|buffer| {
+            result = reader.read_exact(buffer);
+            buffer.len()
+        });
+        result.map(|()| consumer(&bytes))
+    })
+}
+
+/// `read_to_end_inplace` reads all bytes from `reader` into a buffer placed with `inplace_or_alloc_array`
+/// and pass them into the `consumer` closure. `consumer`'s result will be returned as `Ok(result)`.
+///
+/// The buffer has `DEFAULT_IO_BUFFER_LEN` bytes.
+/// If `reader` has more bytes, they are moved into `Vec` and the rest bytes are read into it.
+/// The buffer is zeroed in growing chunks while it's filled, so short readers do not pay for zeroing of all of it.
+///
+/// Errors of `Read::read` (except `ErrorKind::Interrupted` ones) are returned as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::read_to_end_inplace;
+///
+/// let reader: &[u8] = b"short message";
+/// let len = read_to_end_inplace(reader, |bytes: &[u8]| bytes.len()).unwrap();
+/// assert_eq!(len, 13);
+///
+/// let large = vec![7u8; 100_000];
+/// let sum = read_to_end_inplace(&large[..], |bytes: &[u8]| bytes.iter().map(|byte| *byte as u64).sum::<u64>()).unwrap();
+/// assert_eq!(sum, 700_000);
+/// ```
+pub fn read_to_end_inplace<Reader, R, Consumer>(mut reader: Reader, consumer: Consumer) -> io::Result<R>
+    where Reader: Read,
+          Consumer: FnOnce(&[u8]) -> R,
+{
+    inplace_or_alloc_array(DEFAULT_IO_BUFFER_LEN, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let capacity = guard.len();
+        let mut result = Ok(());
+        let (buffer, _) = unsafe {
+            guard.init_with_raw(|ptr, len| {
+                let mut memory = LazyZeroed { ptr, len, zeroed: 0 };
+                let mut filled = 0;
+                while filled < len {
+                    let buffer = memory.zeroed_after(filled);
+                    let available = buffer.len();
+                    match reader.read(buffer) {
+                        Ok(0) => break,
+                        Ok(read) => {
+                            assert!(read <= available, "reader returned more bytes than the buffer holds");
+                            filled += read;
+                        }
+                        Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                        Err(error) => {
+                            result = Err(error);
+                            break;
+                        }
+                    }
+                }
+                filled
+            })
+        };
+        result?;
+        if buffer.len() < capacity {
+            return Ok(consumer(&buffer));
+        }
+        // Buffer is full, so reader may have more bytes
+        let mut probe = [0; PROBE_LEN];
+        let probed = read_to_fill(&mut reader, &mut probe)?;
+        if probed == 0 {
+            return Ok(consumer(&buffer));
+        }
+        let mut vec = Vec::with_capacity(buffer.len() * 2);
+        vec.extend_from_slice(&buffer);
+        vec.extend_from_slice(&probe[..probed]);
+        reader.read_to_end(&mut vec)?;
+        Ok(consumer(&vec))
+    })
+}
+
+/// `copy_inplace` works like `std::io::copy` but uses a buffer of `buffer_len` bytes
+/// placed with `inplace_or_alloc_array`.
+///
+/// Returns count of copied bytes.
+///
+/// The buffer is zeroed in growing chunks while reads fill it, so short readers do not pay for zeroing of all of it.
+///
+/// ### Panics
+///
+/// Panic can be reached when `buffer_len` is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::copy_inplace;
+///
+/// let mut reader: &[u8] = b"some bytes to copy";
+/// let mut writer = Vec::new();
+/// let copied = copy_inplace(&mut reader, &mut writer, 4).unwrap();
+/// assert_eq!(copied, 18);
+/// assert_eq!(writer, b"some bytes to copy");
+/// ```
+pub fn copy_inplace<Reader, Writer>(mut reader: Reader, mut writer: Writer, buffer_len: usize) -> io::Result<u64>
+    where Reader: Read,
+          Writer: Write,
+{
+    assert!(buffer_len != 0, "buffer_len should be greater than zero");
+    inplace_or_alloc_array(buffer_len, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let mut result = Ok(0);
+        unsafe {
+            guard.slice(..buffer_len).init_with_raw(|ptr, len| {
+                let mut memory = LazyZeroed { ptr, len, zeroed: 0 };
+                result = copy_through(&mut reader, &mut writer, &mut memory);
+                memory.zeroed
+            });
+        }
+        result
+    })
+}
+
+/// Copies bytes from `reader` into `writer` through `memory`. Returns count of copied bytes.
+fn copy_through(reader: &mut impl Read, writer: &mut impl Write, memory: &mut LazyZeroed) -> io::Result<u64> {
+    let mut copied = 0;
+    loop {
+        let buffer = unsafe { memory.zeroed_after(0) };
+        let len = match reader.read(buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(len) => len,
+            Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        writer.write_all(&buffer[..len])?;
+        copied += len as u64;
+        // Reader may fill more memory
+        if len == buffer.len() {
+            unsafe { memory.zero_next_chunk() };
+        }
+    }
+}
+
+/// Memory of `len` bytes at `ptr` whose first `zeroed` bytes are zeroed (or initialized otherwise).
+struct LazyZeroed {
+    ptr: *mut u8,
+    len: usize,
+    zeroed: usize,
+}
+
+impl LazyZeroed {
+    /// Zeroes the next chunk of memory. Chunks grow twice each time.
+    ///
+    /// # Safety
+    ///
+    /// Memory should be valid for writes.
+    #[inline]
+    unsafe fn zero_next_chunk(&mut self) {
+        let chunk = self.zeroed.max(MIN_ZEROED_LEN).min(self.len - self.zeroed);
+        write_bytes(self.ptr.add(self.zeroed), 0, chunk);
+        self.zeroed += chunk;
+    }
+
+    /// Get zeroed memory after `start` bytes. If there is no such memory, the next chunk is zeroed.
+    ///
+    /// # Safety
+    ///
+    /// Memory should be valid for writes and `start` should be not greater than `zeroed`.
+    #[inline]
+    unsafe fn zeroed_after(&mut self, start: usize) -> &mut [u8] {
+        if start == self.zeroed {
+            self.zero_next_chunk();
+        }
+        from_raw_parts_mut(self.ptr.add(start), self.zeroed - start)
+    }
+}
+
+/// Reads bytes until `buffer` is full or `reader` has no more bytes.
+/// Returns count of read bytes.
+fn read_to_fill(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(len) => filled += len,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+/// Cursor which writes bytes into memory of `SliceMemoryGuard<u8>`.
+///
+/// Like `std::io::Cursor<&mut [u8]>`, it writes no more bytes than memory holds.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array, InplaceCursor, UninitializedSliceMemoryGuard};
+/// use std::io::Write;
+///
+/// inplace_or_alloc_array(64, |guard: UninitializedSliceMemoryGuard<u8>| {
+///     let mut cursor = InplaceCursor::new(guard.init(|_| 0));
+///     cursor.write_all(&[0x01, 0x02]).unwrap();
+///     write!(cursor, "frame {}", 1).unwrap();
+///     assert_eq!(cursor.written(), b"\x01\x02frame 1");
+///     assert_eq!(cursor.remaining(), 64 - 9);
+/// });
+/// ```
+pub struct InplaceCursor<'a> {
+    guard: SliceMemoryGuard<'a, u8>,
+    position: usize,
+}
+
+impl<'a> InplaceCursor<'a> {
+    /// Create new cursor which writes bytes from the beginning of `guard`'s memory.
+    #[inline]
+    pub fn new(guard: SliceMemoryGuard<'a, u8>) -> Self {
+        Self { guard, position: 0 }
+    }
+
+    /// Get count of written bytes.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get count of bytes which can be written yet.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.guard.len() - self.position
+    }
+
+    /// Get written bytes.
+    #[inline]
+    pub fn written(&self) -> &[u8] {
+        &self.guard[..self.position]
+    }
+
+    /// Get the guard of written bytes and the guard of the rest memory.
+    #[inline]
+    pub fn into_parts(self) -> (SliceMemoryGuard<'a, u8>, SliceMemoryGuard<'a, u8>) {
+        self.guard.split_at(self.position)
+    }
+
+    /// Get the guard of the whole memory.
+    #[inline]
+    pub fn into_inner(self) -> SliceMemoryGuard<'a, u8> {
+        self.guard
+    }
+}
+
+impl<'a> Write for InplaceCursor<'a> {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let len = bytes.len().min(self.remaining());
+        self.guard[self.position..self.position + len].copy_from_slice(&bytes[..len]);
+        self.position += len;
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
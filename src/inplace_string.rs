@@ -0,0 +1,177 @@
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    str::{from_utf8_unchecked, from_utf8_unchecked_mut},
+};
+use alloc::string::String;
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::inplace_vec::InplaceVec;
+
+/// Growable UTF-8 string placed in memory of `UninitializedSliceMemoryGuard<u8>`.
+///
+/// When it outgrows memory of the guard, it moves its bytes into the heap and keeps working with them.
+/// See `InplaceVec`.
+///
+/// ### Example
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array, InplaceString, UninitializedSliceMemoryGuard};
+/// use core::fmt::Write;
+///
+/// inplace_or_alloc_array(64, |guard: UninitializedSliceMemoryGuard<u8>| {
+///     let mut string = InplaceString::new(guard);
+///     string.push_str("key");
+///     string.push(':');
+///     write!(string, "{}", 42).unwrap();
+///     assert_eq!(&*string, "key:42");
+///     assert!(!string.spilled());
+/// });
+/// ```
+pub struct InplaceString<'a> {
+    bytes: InplaceVec<'a, u8>,
+}
+
+impl<'a> InplaceString<'a> {
+    /// Create new empty string in memory of given `guard`.
+    #[inline]
+    pub fn new(guard: UninitializedSliceMemoryGuard<'a, u8>) -> Self {
+        Self { bytes: InplaceVec::new(guard) }
+    }
+
+    /// Get length of the string in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Check if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Get count of bytes the string can hold without moving them.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Check if bytes were moved into the heap.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        self.bytes.spilled()
+    }
+
+    /// Get the string as `str`.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // Only whole UTF-8 sequences are stored
+        unsafe { from_utf8_unchecked(&self.bytes) }
+    }
+
+    /// Get the string as mutable `str`.
+    #[inline]
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { from_utf8_unchecked_mut(&mut self.bytes) }
+    }
+
+    /// Append `char` to the end of the string.
+    #[inline]
+    pub fn push(&mut self, char: char) {
+        self.push_str(char.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Append `string` to the end of the string.
+    #[inline]
+    pub fn push_str(&mut self, string: &str) {
+        self.bytes.extend_from_slice(string.as_bytes())
+    }
+
+    /// Remove the last `char` and return it, or `None` if the string is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<char> {
+        let char = self.as_str().chars().next_back()?;
+        self.bytes.truncate(self.len() - char.len_utf8());
+        Some(char)
+    }
+
+    /// Insert `char` at byte position `index`.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `index` is greater than the string's length or it's not on a `char` boundary.
+    pub fn insert(&mut self, index: usize, char: char) {
+        assert!(self.is_char_boundary(index), "index (is {}) should be on a char boundary", index);
+        // Whole char is appended and then moved, so the string is always valid UTF-8
+        let char_len = char.len_utf8();
+        self.push(char);
+        self.bytes[index..].rotate_right(char_len);
+    }
+
+    /// Keep first `len` bytes of the string and drop the rest.
+    /// Has no effect if `len` is greater than the string's length.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `len` is not on a `char` boundary.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            assert!(self.is_char_boundary(len), "len (is {}) should be on a char boundary", len);
+            self.bytes.truncate(len);
+        }
+    }
+
+    /// Make the string empty.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bytes.clear()
+    }
+
+    /// Convert the string into `String`, moving bytes into the heap if they are not there yet.
+    #[inline]
+    pub fn into_string(self) -> String {
+        unsafe { String::from_utf8_unchecked(self.bytes.into_vec()) }
+    }
+}
+
+impl<'a> Deref for InplaceString<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<'a> DerefMut for InplaceString<'a> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_str()
+    }
+}
+
+impl<'a> fmt::Write for InplaceString<'a> {
+    #[inline]
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.push_str(string);
+        Ok(())
+    }
+
+    #[inline]
+    fn write_char(&mut self, char: char) -> fmt::Result {
+        self.push(char);
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for InplaceString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<'a> fmt::Debug for InplaceString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
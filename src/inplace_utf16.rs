@@ -0,0 +1,100 @@
+use core::{
+    char::{decode_utf16, DecodeUtf16Error},
+    ptr::copy_nonoverlapping,
+    str::from_utf8_unchecked,
+};
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::alloc_array::inplace_or_alloc_array;
+
+/// Maximal count of UTF-8 bytes per one UTF-16 code unit.
+const MAX_UTF8_LEN_PER_UTF16_UNIT: usize = 3;
+
+/// `inplace_utf16` encodes `string` into UTF-16 code units placed with `inplace_or_alloc_array`
+/// and pass them into the `consumer` closure. `consumer`'s result will be returned.
+///
+/// If `nul_terminated` is `true`, zero code unit is appended.
+///
+/// String of `n` bytes takes no more than `n` UTF-16 code units,
+/// so the array of `string.len()` code units is placed and `consumer` takes exactly encoded ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_utf16;
+///
+/// inplace_utf16("añ😀", false, |units: &[u16]| {
+///     assert_eq!(units, &[0x61, 0xF1, 0xD83D, 0xDE00]);
+/// });
+/// inplace_utf16("a", true, |units: &[u16]| {
+///     assert_eq!(units, &[0x61, 0]);
+/// });
+/// ```
+pub fn inplace_utf16<R, Consumer>(string: &str, nul_terminated: bool, consumer: Consumer) -> R
+    where Consumer: FnOnce(&[u16]) -> R
+{
+    let nul = if nul_terminated { Some(0) } else { None };
+    inplace_or_alloc_array(string.len() + nul_terminated as usize, |guard: UninitializedSliceMemoryGuard<u16>| {
+        match guard.init_with_dyn_iter(string.encode_utf16().chain(nul)) {
+            Ok(units) => consumer(&units),
+            Err(units) => consumer(&units),
+        }
+    })
+}
+
+/// `inplace_utf8_from_utf16` decodes UTF-16 code `units` into UTF-8 string placed with `inplace_or_alloc_array`
+/// and pass it into the `consumer` closure. `consumer`'s result will be returned.
+///
+/// If `units` contain unpaired surrogate, `consumer` takes `Err(DecodeUtf16Error)` of the first one.
+///
+/// Each code unit takes no more than 3 bytes of UTF-8,
+/// so the array of `3 * units.len()` bytes is placed and `consumer` takes exactly decoded ones.
+///
+/// ### Panics
+///
+/// Panic can be reached when `3 * units.len()` overflows `usize`.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::inplace_utf8_from_utf16;
+///
+/// inplace_utf8_from_utf16(&[0x61, 0xF1, 0xD83D, 0xDE00], |string| {
+///     assert_eq!(string, Ok("añ😀"));
+/// });
+/// inplace_utf8_from_utf16(&[0x61, 0xD83D], |string| {
+///     assert_eq!(string.unwrap_err().unpaired_surrogate(), 0xD83D);
+/// });
+/// ```
+pub fn inplace_utf8_from_utf16<R, Consumer>(units: &[u16], consumer: Consumer) -> R
+    where Consumer: FnOnce(Result<&str, DecodeUtf16Error>) -> R
+{
+    let max_len = units.len().checked_mul(MAX_UTF8_LEN_PER_UTF16_UNIT).expect("capacity overflow");
+    inplace_or_alloc_array(max_len, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let mut error = None;
+        let (string, _) = unsafe {
+            guard.init_with_raw(|ptr, _| {
+                let mut len = 0;
+                for decoded in decode_utf16(units.iter().cloned()) {
+                    match decoded {
+                        Ok(char) => {
+                            let mut buffer = [0; 4];
+                            let bytes = char.encode_utf8(&mut buffer).as_bytes();
+                            copy_nonoverlapping(bytes.as_ptr(), ptr.add(len), bytes.len());
+                            len += bytes.len();
+                        }
+                        Err(decode_error) => {
+                            error = Some(decode_error);
+                            break;
+                        }
+                    }
+                }
+                len
+            })
+        };
+        match error {
+            Some(error) => consumer(Err(error)),
+            // Buffer contains concatenation of encoded chars
+            None => consumer(Ok(unsafe { from_utf8_unchecked(&string) })),
+        }
+    })
+}
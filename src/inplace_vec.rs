@@ -0,0 +1,449 @@
+use core::{
+    fmt,
+    iter::FusedIterator,
+    mem::{MaybeUninit, replace},
+    ops::{Deref, DerefMut, RangeBounds, Bound},
+    ptr::{copy, copy_nonoverlapping, drop_in_place, read, write},
+    slice,
+};
+use alloc::vec::{self, Vec};
+use crate::guards::UninitializedSliceMemoryGuard;
+
+/// Growable vector placed in memory of `UninitializedSliceMemoryGuard`.
+///
+/// When it outgrows memory of the guard, it moves its elements into a new `Vec` and keeps working with it.
+///
+/// ### Example
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array, InplaceVec, UninitializedSliceMemoryGuard};
+///
+/// inplace_or_alloc_array(32, |guard: UninitializedSliceMemoryGuard<u32>| {
+///     let mut vec = InplaceVec::new(guard);
+///     vec.extend(0..10);
+///     vec.retain(|item| item % 2 == 0);
+///     assert_eq!(&*vec, &[0, 2, 4, 6, 8]);
+///     assert!(!vec.spilled());
+///
+///     // Too many elements for the stack, so they are moved into the heap
+///     vec.extend(0..100);
+///     assert!(vec.spilled());
+///     assert_eq!(vec.len(), 105);
+/// });
+/// ```
+pub struct InplaceVec<'a, T> {
+    storage: Storage<'a, T>,
+}
+
+enum Storage<'a, T> {
+    Inline(InlineBuffer<'a, T>),
+    Heap(Vec<T>),
+}
+
+/// Initialized prefix of memory. Drops it on drop.
+struct InlineBuffer<'a, T> {
+    memory: &'a mut [MaybeUninit<T>],
+    len: usize,
+}
+
+impl<'a, T> InplaceVec<'a, T> {
+    /// Create new empty vector in memory of given `guard`.
+    #[inline]
+    pub fn new(guard: UninitializedSliceMemoryGuard<'a, T>) -> Self {
+        Self {
+            storage: Storage::Inline(InlineBuffer { memory: guard.into_memory(), len: 0 }),
+        }
+    }
+
+    /// Get count of elements in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(buffer) => buffer.len,
+            Storage::Heap(vec) => vec.len(),
+        }
+    }
+
+    /// Check if the vector contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get count of elements the vector can hold without moving them.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(buffer) => buffer.memory.len(),
+            Storage::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    /// Check if elements were moved into the heap.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        match &self.storage {
+            Storage::Inline(_) => false,
+            Storage::Heap(_) => true,
+        }
+    }
+
+    /// Append `value` to the end of the vector.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        match &mut self.storage {
+            Storage::Inline(buffer) if buffer.len < buffer.memory.len() => unsafe {
+                write(buffer.memory.get_unchecked_mut(buffer.len).as_mut_ptr(), value);
+                buffer.len += 1;
+            },
+            _ => self.spill(1).push(value),
+        }
+    }
+
+    /// Clone and append all elements of `items` to the end of the vector.
+    pub fn extend_from_slice(&mut self, items: &[T])
+        where T: Clone
+    {
+        match &mut self.storage {
+            Storage::Inline(buffer) if items.len() <= buffer.memory.len() - buffer.len => {
+                for item in items {
+                    // Length is increased after each element, so cloned elements are dropped if `clone` panics
+                    unsafe { write(buffer.memory.get_unchecked_mut(buffer.len).as_mut_ptr(), item.clone()) };
+                    buffer.len += 1;
+                }
+            }
+            _ => self.spill(items.len()).extend_from_slice(items),
+        }
+    }
+
+    /// Remove the last element and return it, or `None` if the vector is empty.
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(buffer) => {
+                if buffer.len == 0 {
+                    return None;
+                }
+                buffer.len -= 1;
+                Some(unsafe { read(buffer.memory.get_unchecked(buffer.len).as_ptr()) })
+            }
+            Storage::Heap(vec) => vec.pop(),
+        }
+    }
+
+    /// Insert `value` at position `index`, shifting all elements after it to the right.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len();
+        assert!(index <= len, "insertion index (is {}) should be <= len (is {})", index, len);
+        match &mut self.storage {
+            Storage::Inline(buffer) if buffer.len < buffer.memory.len() => unsafe {
+                let ptr = buffer.memory.as_mut_ptr().add(index) as *mut T;
+                copy(ptr, ptr.add(1), len - index);
+                write(ptr, value);
+                buffer.len += 1;
+            },
+            _ => self.spill(1).insert(index, value),
+        }
+    }
+
+    /// Remove and return the element at position `index`, shifting all elements after it to the left.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "removal index (is {}) should be < len (is {})", index, len);
+        match &mut self.storage {
+            Storage::Inline(buffer) => unsafe {
+                let ptr = buffer.memory.as_mut_ptr().add(index) as *mut T;
+                let value = read(ptr);
+                copy(ptr.add(1), ptr, len - index - 1);
+                buffer.len -= 1;
+                value
+            },
+            Storage::Heap(vec) => vec.remove(index),
+        }
+    }
+
+    /// Keep first `len` elements and drop the rest.
+    /// Has no effect if `len` is greater than the vector's length.
+    pub fn truncate(&mut self, len: usize) {
+        match &mut self.storage {
+            Storage::Inline(buffer) => buffer.truncate(len),
+            Storage::Heap(vec) => vec.truncate(len),
+        }
+    }
+
+    /// Drop all elements of the vector.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+
+    /// Keep only elements for which `f` returns `true`.
+    /// Order of kept elements is preserved.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let buffer = match &mut self.storage {
+            Storage::Inline(buffer) => buffer,
+            Storage::Heap(vec) => return vec.retain(f),
+        };
+        // If `f` or `drop` panics, the guard keeps processed elements and moves the rest to them
+        let mut retain = Retain {
+            original_len: buffer.len,
+            processed: 0,
+            deleted: 0,
+            buffer,
+        };
+        retain.buffer.len = 0;
+        while retain.processed < retain.original_len {
+            unsafe {
+                let base = retain.buffer.memory.as_mut_ptr() as *mut T;
+                let current = base.add(retain.processed);
+                if !f(&*current) {
+                    retain.processed += 1;
+                    retain.deleted += 1;
+                    drop_in_place(current);
+                } else {
+                    if retain.deleted > 0 {
+                        copy_nonoverlapping(current, current.sub(retain.deleted), 1);
+                    }
+                    retain.processed += 1;
+                }
+            }
+        }
+    }
+
+    /// Remove given `range` of elements and return them by iterator.
+    ///
+    /// Elements which are not consumed by the iterator are dropped when it is dropped.
+    ///
+    /// ### Panics
+    ///
+    /// Panic can be reached when given `range` is out of the vector's range.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, T> {
+        let buffer = match &mut self.storage {
+            Storage::Inline(buffer) => buffer,
+            Storage::Heap(vec) => return Drain { inner: DrainInner::Heap(vec.drain(range)) },
+        };
+        let len = buffer.len;
+        let start = match range.start_bound() {
+            Bound::Excluded(n) => n.checked_add(1).expect("range start overflows usize"),
+            Bound::Included(n) => *n,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Excluded(n) => *n,
+            Bound::Included(n) => n.checked_add(1).expect("range end overflows usize"),
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end, "range start (is {}) should be <= range end (is {})", start, end);
+        assert!(end <= len, "range end (is {}) should be <= len (is {})", end, len);
+        // If the iterator is leaked, drained and tail elements are leaked too
+        buffer.len = start;
+        Drain {
+            inner: DrainInner::Inline {
+                memory: &mut *buffer.memory,
+                len: &mut buffer.len,
+                next: start,
+                end,
+                tail_start: end,
+                tail_end: len,
+            },
+        }
+    }
+
+    /// Move elements into the heap if they are not there yet and reserve space for `additional` more of them.
+    #[cold]
+    fn spill(&mut self, additional: usize) -> &mut Vec<T> {
+        if let Storage::Inline(buffer) = &mut self.storage {
+            let len = buffer.len;
+            let capacity = len.saturating_add(additional).max(len.saturating_mul(2));
+            let mut vec = Vec::with_capacity(capacity);
+            unsafe {
+                copy_nonoverlapping(buffer.memory.as_ptr() as *const T, vec.as_mut_ptr(), len);
+                buffer.len = 0;
+                vec.set_len(len);
+            }
+            self.storage = Storage::Heap(vec);
+        }
+        match &mut self.storage {
+            Storage::Heap(vec) => {
+                vec.reserve(additional);
+                vec
+            }
+            Storage::Inline(_) => unreachable!(),
+        }
+    }
+
+    /// Convert the vector into `Vec`, moving elements into the heap if they are not there yet.
+    #[inline]
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.spill(0);
+        match replace(&mut self.storage, Storage::Heap(Vec::new())) {
+            Storage::Heap(vec) => vec,
+            Storage::Inline(_) => unreachable!(),
+        }
+    }
+}
+
+impl<'a, T> InlineBuffer<'a, T> {
+    #[inline]
+    fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let old_len = self.len;
+            self.len = len;
+            unsafe {
+                drop_in_place(&mut self.memory[len..old_len] as *mut [MaybeUninit<T>] as *mut [T]);
+            }
+        }
+    }
+}
+
+impl<'a, T> Drop for InlineBuffer<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.truncate(0);
+    }
+}
+
+struct Retain<'b, 'a, T> {
+    buffer: &'b mut InlineBuffer<'a, T>,
+    original_len: usize,
+    processed: usize,
+    deleted: usize,
+}
+
+impl<'b, 'a, T> Drop for Retain<'b, 'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let base = self.buffer.memory.as_mut_ptr() as *mut T;
+            if self.deleted > 0 {
+                copy(base.add(self.processed), base.add(self.processed - self.deleted), self.original_len - self.processed);
+            }
+        }
+        self.buffer.len = self.original_len - self.deleted;
+    }
+}
+
+impl<'a, T> Deref for InplaceVec<'a, T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match &self.storage {
+            Storage::Inline(buffer) => unsafe {
+                slice::from_raw_parts(buffer.memory.as_ptr() as *const T, buffer.len)
+            },
+            Storage::Heap(vec) => vec,
+        }
+    }
+}
+
+impl<'a, T> DerefMut for InplaceVec<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.storage {
+            Storage::Inline(buffer) => unsafe {
+                slice::from_raw_parts_mut(buffer.memory.as_mut_ptr() as *mut T, buffer.len)
+            },
+            Storage::Heap(vec) => vec,
+        }
+    }
+}
+
+impl<'a, T> Extend<T> for InplaceVec<'a, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for InplaceVec<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Iterator returned by `InplaceVec::drain`.
+pub struct Drain<'b, T> {
+    inner: DrainInner<'b, T>,
+}
+
+enum DrainInner<'b, T> {
+    Inline {
+        memory: &'b mut [MaybeUninit<T>],
+        len: &'b mut usize,
+        next: usize,
+        end: usize,
+        tail_start: usize,
+        tail_end: usize,
+    },
+    Heap(vec::Drain<'b, T>),
+}
+
+impl<'b, T> Iterator for Drain<'b, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        match &mut self.inner {
+            DrainInner::Inline { memory, next, end, .. } => {
+                if *next == *end {
+                    return None;
+                }
+                *next += 1;
+                Some(unsafe { read(memory.get_unchecked(*next - 1).as_ptr()) })
+            }
+            DrainInner::Heap(drain) => drain.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            DrainInner::Inline { next, end, .. } => (end - next, Some(end - next)),
+            DrainInner::Heap(drain) => drain.size_hint(),
+        }
+    }
+}
+
+impl<'b, T> DoubleEndedIterator for Drain<'b, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        match &mut self.inner {
+            DrainInner::Inline { memory, next, end, .. } => {
+                if *next == *end {
+                    return None;
+                }
+                *end -= 1;
+                Some(unsafe { read(memory.get_unchecked(*end).as_ptr()) })
+            }
+            DrainInner::Heap(drain) => drain.next_back(),
+        }
+    }
+}
+
+impl<'b, T> ExactSizeIterator for Drain<'b, T> {}
+
+impl<'b, T> FusedIterator for Drain<'b, T> {}
+
+impl<'b, T> Drop for Drain<'b, T> {
+    fn drop(&mut self) {
+        if let DrainInner::Inline { memory, len, next, end, tail_start, tail_end } = &mut self.inner {
+            let (next, end, tail_start, tail_end) = (*next, *end, *tail_start, *tail_end);
+            // If `drop` of a drained element panics, the tail is leaked
+            unsafe {
+                drop_in_place(&mut memory[next..end] as *mut [MaybeUninit<T>] as *mut [T]);
+                let base = memory.as_mut_ptr();
+                copy(base.add(tail_start), base.add(**len), tail_end - tail_start);
+            }
+            **len += tail_end - tail_start;
+        }
+    }
+}
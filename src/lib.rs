@@ -18,6 +18,7 @@
 //!     |mut uninit_guard: UninitializedSliceMemoryGuard<u16>| { // and this is consumer of uninitialized memory
 //!
 //!         // Size of stack-allocated memory can be more or equal to requested, but never less.
+//!         # #[cfg(not(feature = "alloca"))]
 //!         assert_eq!(160, uninit_guard.len());
 //!
 //!         {
@@ -29,6 +30,7 @@
 //!             // Memory now contains elements [1, 2, ..., 160]
 //!             // Lets check it. Sum of [1, 2, ..., 160] = 12880
 //!             let sum: u16 = init_guard.iter().sum();
+//!             # #[cfg(not(feature = "alloca"))]
 //!             assert_eq!(sum, 12880);
 //!         }
 //!
@@ -38,6 +40,7 @@
 //!             // Memory now contains elements [0, 2, 4, ..., 318]
 //!             // Lets check it. Sum of [0, 2, 4, ..., 318] = 25440
 //!             let sum: u16 = init_guard.iter().sum();
+//!             # #[cfg(not(feature = "alloca"))]
 //!             assert_eq!(sum, 25440);
 //!         }
 //!     }
@@ -49,15 +52,77 @@
 //! Because allocation on the stack (i.e. placing variables) is **MUCH FASTER** then usual
 //! allocating in the heap.
 //!
+//! ## Features
+//!
+//! * `compact` - place arrays in a small set of type-erased frames shared by all types and call sites.
+//!   By default, each call of `try_inplace_array` generates a copy of consumer's code for every size class.
+//!   With this feature, it generates just one copy, so binaries are smaller.
+//!   Placed arrays have the same length but may take a bit more stack memory.
+//! * `alloca` - place arrays of exactly requested length by moving the stack pointer
+//!   on x86_64 and aarch64 Linux (see `Alloca`). `try_inplace_array`, `inplace_or_alloc_array`
+//!   and other functions use it instead of `Linear32` size classes. On other targets nothing changes.
+//!   Requires Rust 1.88 or newer.
+//! * `std` - on Linux, allocate arrays in the heap when placing them would leave too little
+//!   of the current thread's stack (see `remaining_stack` and `set_stack_safety_margin`).
+//!   Also, limit stack memory taken by nested placements on each thread (see `set_thread_stack_budget`).
+//!   Also, make C strings of paths (see `inplace_os_cstr`).
+//!   Also, read and write with buffers placed on the stack (see `read_to_end_inplace`, `copy_inplace` and `InplaceCursor`).
+//! * `scratch-pool` - reuse heap memory of arrays which are too large for the stack (see `ThreadScratchPool`).
+//!   `alloc_array`, `inplace_or_alloc_array` and other functions use it instead of `Global`. Enables `std`.
+//!
+//! ## Minimum supported Rust version
+//!
+//! The crate is built with Rust 1.74 or newer, except for `alloca` feature which requires Rust 1.88.
+//!
 
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod guards;
+mod size_classes;
 mod fixed_array;
+mod scratch_allocator;
 mod alloc_array;
+mod stack_budget;
+mod inplace_vec;
+mod inplace_chunks;
+mod inplace_arrays;
+mod inplace_format;
+mod inplace_cstr;
+mod inplace_utf16;
+mod inplace_string;
+#[cfg(feature = "compact")]
+mod compact_array;
+#[cfg(feature = "alloca")]
+mod alloca_array;
+#[cfg(feature = "std")]
+mod stack_headroom;
+#[cfg(feature = "scratch-pool")]
+mod scratch_pool;
+#[cfg(feature = "std")]
+mod inplace_io;
 
 pub use guards::*;
+pub use size_classes::*;
 pub use fixed_array::*;
+pub use scratch_allocator::*;
 pub use alloc_array::*;
+pub use stack_budget::*;
+pub use inplace_vec::*;
+pub use inplace_chunks::*;
+pub use inplace_arrays::*;
+pub use inplace_format::*;
+pub use inplace_cstr::*;
+pub use inplace_utf16::*;
+pub use inplace_string::*;
+#[cfg(feature = "alloca")]
+pub use alloca_array::*;
+#[cfg(feature = "std")]
+pub use stack_headroom::*;
+#[cfg(feature = "scratch-pool")]
+pub use scratch_pool::*;
+#[cfg(feature = "std")]
+pub use inplace_io::*;
@@ -0,0 +1,94 @@
+use core::{
+    alloc::Layout,
+    ptr::NonNull,
+};
+
+/// Allocator used by `alloc_array_in` and `inplace_or_alloc_array_in` to allocate arrays
+/// that are too large to place on the stack.
+///
+/// It's a simplified version of unstable `core::alloc::Allocator`.
+///
+/// ### Safety
+///
+/// Memory returned by `allocate` should be valid for reads and writes of `layout.size()` bytes,
+/// aligned to `layout.align()` and not used by anything else until it is passed into `deallocate`.
+///
+/// ### Example
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array_in, Global, ScratchAllocator};
+/// use core::{alloc::Layout, cell::Cell, ptr::NonNull};
+///
+/// /// Counts allocations and forwards them into the global allocator
+/// struct Counting {
+///     count: Cell<usize>,
+/// }
+///
+/// unsafe impl ScratchAllocator for Counting {
+///     fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+///         self.count.set(self.count.get() + 1);
+///         Global.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         Global.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let allocator = Counting { count: Cell::new(0) };
+/// let len = inplace_or_alloc_array_in(&allocator, 100_000, |guard| guard.init(|i| i as u8).len());
+/// assert_eq!(len, 100_000);
+/// assert_eq!(allocator.count.get(), 1);
+/// ```
+pub unsafe trait ScratchAllocator {
+    /// Allocate memory for given non-zero-sized `layout`.
+    /// `None` should be returned if allocation fails.
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Deallocate memory previously allocated by `allocate`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` should be allocated by this allocator with the same `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Get layout of memory actually allocated by `allocate` for `layout`.
+    /// It's reported by `try_alloc_array_in` when allocation fails.
+    #[inline]
+    fn allocated_layout(&self, layout: Layout) -> Layout {
+        layout
+    }
+}
+
+unsafe impl<A: ScratchAllocator + ?Sized> ScratchAllocator for &A {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+
+    #[inline]
+    fn allocated_layout(&self, layout: Layout) -> Layout {
+        (**self).allocated_layout(layout)
+    }
+}
+
+/// The global allocator. Used by `alloc_array` and `inplace_or_alloc_array`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Global;
+
+unsafe impl ScratchAllocator for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        alloc::alloc::dealloc(ptr.as_ptr(), layout)
+    }
+}
@@ -0,0 +1,184 @@
+use core::{
+    alloc::Layout,
+    cell::{Cell, RefCell},
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use alloc::vec::Vec;
+use std::thread_local;
+use crate::scratch_allocator::{ScratchAllocator, Global};
+
+/// Default amount of memory (in bytes) kept by the scratch pool of each thread.
+pub const DEFAULT_SCRATCH_POOL_CAPACITY: usize = 256 * 1024;
+
+/// Largest buffer (in bytes, after rounding up) kept by scratch pools.
+///
+/// Larger buffers are allocated with `Global` as is regardless of `scratch_pool_capacity()`.
+pub const MAX_SCRATCH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Buffers smaller than this are rounded up to it.
+const MIN_BUFFER_SIZE: usize = 64;
+
+static SCRATCH_POOL_CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_SCRATCH_POOL_CAPACITY);
+
+/// Set amount of memory (in bytes) kept by the scratch pool of each thread.
+///
+/// Buffers returned into a full pool are deallocated.
+/// Pools that already keep more memory are not trimmed, use `trim_scratch_pool` for that.
+#[inline]
+pub fn set_scratch_pool_capacity(bytes: usize) {
+    SCRATCH_POOL_CAPACITY.store(bytes, Ordering::Relaxed);
+}
+
+/// Get amount of memory (in bytes) kept by the scratch pool of each thread.
+#[inline]
+pub fn scratch_pool_capacity() -> usize {
+    SCRATCH_POOL_CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Get amount of memory (in bytes) kept by the scratch pool of the current thread.
+#[inline]
+pub fn scratch_pool_size() -> usize {
+    SCRATCH_POOL.try_with(|pool| pool.size.get()).unwrap_or(0)
+}
+
+/// Deallocate buffers of the current thread's scratch pool until it keeps no more than `bytes` bytes.
+/// The largest buffers are deallocated first.
+#[inline]
+pub fn trim_scratch_pool(bytes: usize) {
+    let _ = SCRATCH_POOL.try_with(|pool| pool.trim(bytes));
+}
+
+/// Deallocate all buffers of the current thread's scratch pool.
+#[inline]
+pub fn clear_scratch_pool() {
+    trim_scratch_pool(0);
+}
+
+/// Allocator that reuses buffers of the current thread's scratch pool.
+///
+/// Sizes of buffers are rounded up to powers of two, and buffers are kept by their size and alignment.
+/// Deallocated buffers are returned into the pool while it keeps no more than `scratch_pool_capacity()` bytes.
+/// Buffers larger than `MAX_SCRATCH_BUFFER_SIZE` after rounding up are never kept,
+/// so they are allocated with `Global` as is.
+///
+/// Whether a buffer is rounded up depends only on its layout, so a buffer can be deallocated on any thread.
+///
+/// With `scratch-pool` feature enabled it is used by `alloc_array` and `inplace_or_alloc_array`,
+/// so arrays which are too large for the stack do not hit the global allocator in loops.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{alloc_array, clear_scratch_pool, scratch_pool_size, UninitializedSliceMemoryGuard};
+///
+/// let first = alloc_array(10_000, |guard: UninitializedSliceMemoryGuard<u8>| guard.init(|_| 0).as_ptr());
+/// assert_eq!(scratch_pool_size(), 16 * 1024);
+/// // The same buffer is used again
+/// let second = alloc_array(10_000, |guard: UninitializedSliceMemoryGuard<u8>| guard.init(|_| 0).as_ptr());
+/// assert_eq!(first, second);
+///
+/// clear_scratch_pool();
+/// assert_eq!(scratch_pool_size(), 0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ThreadScratchPool;
+
+unsafe impl ScratchAllocator for ThreadScratchPool {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        match buffer_layout(layout) {
+            Some(layout) => SCRATCH_POOL.try_with(|pool| pool.take(layout))
+                .ok()
+                .flatten()
+                .or_else(|| Global.allocate(layout)),
+            None => Global.allocate(layout),
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        match buffer_layout(layout) {
+            Some(layout) => {
+                if !SCRATCH_POOL.try_with(|pool| pool.keep(ptr, layout)).unwrap_or(false) {
+                    Global.deallocate(ptr, layout);
+                }
+            }
+            None => Global.deallocate(ptr, layout),
+        }
+    }
+
+    #[inline]
+    fn allocated_layout(&self, layout: Layout) -> Layout {
+        buffer_layout(layout).unwrap_or(layout)
+    }
+}
+
+/// Layout of the buffer allocated for `layout` if it can be kept in the pool.
+#[inline]
+fn buffer_layout(layout: Layout) -> Option<Layout> {
+    let size = layout.size().max(MIN_BUFFER_SIZE).checked_next_power_of_two()?;
+    if size > MAX_SCRATCH_BUFFER_SIZE {
+        return None;
+    }
+    Layout::from_size_align(size, layout.align()).ok()
+}
+
+thread_local! {
+    static SCRATCH_POOL: ScratchPool = const {
+        ScratchPool {
+            buffers: RefCell::new(Vec::new()),
+            size: Cell::new(0),
+        }
+    };
+}
+
+struct ScratchPool {
+    buffers: RefCell<Vec<(NonNull<u8>, Layout)>>,
+    size: Cell<usize>,
+}
+
+impl ScratchPool {
+    fn take(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let mut buffers = self.buffers.try_borrow_mut().ok()?;
+        let index = buffers.iter().rposition(|(_, buffer_layout)| *buffer_layout == layout)?;
+        let (ptr, _) = buffers.swap_remove(index);
+        self.size.set(self.size.get() - layout.size());
+        Some(ptr)
+    }
+
+    fn keep(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        if self.size.get().saturating_add(layout.size()) > scratch_pool_capacity() {
+            return false;
+        }
+        match self.buffers.try_borrow_mut() {
+            Ok(mut buffers) => {
+                buffers.push((ptr, layout));
+                self.size.set(self.size.get() + layout.size());
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn trim(&self, bytes: usize) {
+        let mut buffers = match self.buffers.try_borrow_mut() {
+            Ok(buffers) => buffers,
+            Err(_) => return,
+        };
+        buffers.sort_unstable_by_key(|(_, layout)| layout.size());
+        while self.size.get() > bytes {
+            match buffers.pop() {
+                Some((ptr, layout)) => {
+                    self.size.set(self.size.get() - layout.size());
+                    unsafe { Global.deallocate(ptr, layout) };
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+impl Drop for ScratchPool {
+    fn drop(&mut self) {
+        self.trim(0);
+    }
+}
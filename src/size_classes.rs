@@ -0,0 +1,523 @@
+use crate::guards::UninitializedSliceMemoryGuard;
+#[cfg(feature = "compact")]
+use crate::compact_array::place_compact;
+#[cfg(feature = "alloca")]
+use crate::alloca_array::Alloca;
+use core::mem::{MaybeUninit, size_of};
+
+/// Strategy of choosing the length of stack-placed array for requested size.
+///
+/// Arrays can be placed on the stack only with a length known at compile time,
+/// so every size class is a separate fixed-size array and a separate copy of the consumer's code.
+/// More classes mean less wasted stack memory but more code.
+/// Classes which do not fit the stack limit generate no code (see `place_with_limit`).
+///
+/// This crate provides the following strategies:
+///
+/// * `Linear32` (used by default without `alloca` feature) - exact sizes up to 32, then multiples of 32 up to 4096,
+///   then powers of two up to 65536;
+/// * `PowersOfTwo` - powers of two up to 65536;
+/// * `ExactThenGeometric` - exact sizes up to 64, then sizes growing by a factor about 1.5 up to 65536;
+/// * `Alloca` (with `alloca` feature, used by default then) - exact sizes placed by moving the stack pointer.
+///
+/// With `compact` feature enabled built-in strategies place arrays in type-erased frames
+/// shared by all types. Length of placed array stays the same.
+///
+/// ### Implementing
+///
+/// `place` should place an array of exactly `class_len(size)` items (for example,
+/// with `inplace_fixed_array`) and return `Err(consumer)` if the array cannot be placed.
+/// It should always do so when `class_len(size)` is `None`.
+///
+/// ```rust
+/// use inplace_it::{
+///     inplace_fixed_array,
+///     inplace_or_alloc_array_with_size_classes,
+///     SizeClasses,
+///     UninitializedSliceMemoryGuard,
+///     DEFAULT_STACK_LIMIT_BYTES,
+/// };
+///
+/// /// Places only arrays of 16 or 256 items
+/// struct TwoClasses;
+///
+/// impl SizeClasses for TwoClasses {
+///     fn class_len(size: usize) -> Option<usize> {
+///         match size {
+///             0..=16 => Some(16),
+///             17..=256 => Some(256),
+///             _ => None,
+///         }
+///     }
+///
+///     fn place<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+///         where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+///     {
+///         match size {
+///             0..=16 => Ok(inplace_fixed_array::<T, 16, R, Consumer>(consumer)),
+///             17..=256 => Ok(inplace_fixed_array::<T, 256, R, Consumer>(consumer)),
+///             _ => Err(consumer),
+///         }
+///     }
+/// }
+///
+/// let len = inplace_or_alloc_array_with_size_classes::<u8, TwoClasses, DEFAULT_STACK_LIMIT_BYTES, _, _>(
+///     20,
+///     |guard| guard.len(),
+/// );
+/// assert_eq!(len, 256);
+/// ```
+pub trait SizeClasses {
+    /// Returns the length of array placed for `size` requested items
+    /// or `None` if arrays of that size are never placed.
+    ///
+    /// Returned length should never be less than `size`.
+    fn class_len(size: usize) -> Option<usize>;
+
+    /// Places an array of `class_len(size)` items on the stack and pass the guard of memory into the
+    /// `consumer` closure. `consumer`'s result will be returned as `Ok(result)`.
+    ///
+    /// If the array cannot be placed (for example, `class_len(size)` is `None`) then `Err(consumer)` will be returned.
+    fn place<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R;
+
+    /// Works like `place` but returns `Err(consumer)` if the array takes more than `LIMIT_BYTES` bytes.
+    ///
+    /// Built-in strategies do not generate code for classes which never fit `LIMIT_BYTES`.
+    /// It's used by `try_inplace_array_with_size_classes`.
+    #[inline]
+    fn place_with_limit<T, const LIMIT_BYTES: usize, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+        where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+    {
+        match Self::class_len(size) {
+            Some(len) if len.saturating_mul(size_of::<T>()) <= LIMIT_BYTES => Self::place(size, consumer),
+            _ => Err(consumer),
+        }
+    }
+}
+
+/// Checks at compile time that an array of `N` items of `T` takes no more than `LIMIT_BYTES` bytes.
+#[cfg(not(feature = "compact"))]
+struct FitsLimit<T, const N: usize, const LIMIT_BYTES: usize>(core::marker::PhantomData<T>);
+
+#[cfg(not(feature = "compact"))]
+impl<T, const N: usize, const LIMIT_BYTES: usize> FitsLimit<T, N, LIMIT_BYTES> {
+    const VALUE: bool = match N.checked_mul(size_of::<T>()) {
+        Some(bytes) => bytes <= LIMIT_BYTES,
+        None => false,
+    };
+}
+
+/// `inplace_fixed_array` places an array of exactly `N` items of `T` on the stack and pass the guard of memory
+/// into the `consumer` closure. `consumer`'s result will be returned.
+///
+/// It does not check any limits, so it is mostly useful for implementing `SizeClasses`.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_fixed_array, UninitializedSliceMemoryGuard};
+///
+/// let len = inplace_fixed_array::<u16, 10, _, _>(|guard: UninitializedSliceMemoryGuard<u16>| guard.len());
+/// assert_eq!(len, 10);
+/// ```
+#[inline(never)]
+pub fn inplace_fixed_array<T, const N: usize, R, Consumer>(consumer: Consumer) -> R
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    unsafe {
+        let mut memory: [MaybeUninit<T>; N] = MaybeUninit::uninit().assume_init();
+        consumer(UninitializedSliceMemoryGuard::new(&mut memory))
+    }
+}
+
+/// Size classes used by `try_inplace_array`, `inplace_or_alloc_array` and other functions
+/// which do not take `SizeClasses`.
+#[cfg(not(feature = "alloca"))]
+pub(crate) type DefaultSizeClasses = Linear32;
+
+/// Size classes used by `try_inplace_array`, `inplace_or_alloc_array` and other functions
+/// which do not take `SizeClasses`.
+#[cfg(feature = "alloca")]
+pub(crate) type DefaultSizeClasses = Alloca;
+
+/// Largest size for which `DefaultSizeClasses` place an array of no more than `max_len` items.
+#[cfg(all(feature = "alloca", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[inline]
+pub(crate) fn max_default_class_size(max_len: usize) -> usize {
+    max_len
+}
+
+/// Largest size for which `DefaultSizeClasses` place an array of no more than `max_len` items.
+#[cfg(not(all(feature = "alloca", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+#[inline]
+pub(crate) fn max_default_class_size(max_len: usize) -> usize {
+    // Bounds of `Linear32` classes
+    match max_len {
+        0..=32 => max_len,
+        33..=4095 => max_len / 32 * 32,
+        4096..=65535 => 1 << (usize::BITS - 1 - max_len.leading_zeros()),
+        _ => 65536,
+    }
+}
+
+macro_rules! size_classes {
+    ($(#[$meta: meta])* $name: ident { $($pattern: pat => $len: literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+        pub struct $name;
+
+        impl SizeClasses for $name {
+            #[inline]
+            fn class_len(size: usize) -> Option<usize> {
+                match size {
+                    $($pattern => Some($len),)*
+                    _ => None,
+                }
+            }
+
+            #[inline]
+            fn place<T, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+                where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+            {
+                #[cfg(feature = "compact")]
+                match Self::class_len(size) {
+                    Some(len) => place_compact(len, consumer),
+                    None => Err(consumer),
+                }
+                #[cfg(not(feature = "compact"))]
+                {
+                    let result = match size {
+                        $($pattern => inplace_fixed_array::<T, $len, R, Consumer>(consumer),)*
+                        _ => return Err(consumer),
+                    };
+                    Ok(result)
+                }
+            }
+
+            #[cfg(not(feature = "compact"))]
+            #[inline]
+            fn place_with_limit<T, const LIMIT_BYTES: usize, R, Consumer>(size: usize, consumer: Consumer) -> Result<R, Consumer>
+                where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+            {
+                // Conditions are constant, so arrays which do not fit are not even instantiated
+                let result = match size {
+                    $($pattern => if FitsLimit::<T, $len, LIMIT_BYTES>::VALUE {
+                        inplace_fixed_array::<T, $len, R, Consumer>(consumer)
+                    } else {
+                        return Err(consumer);
+                    },)*
+                    _ => return Err(consumer),
+                };
+                Ok(result)
+            }
+        }
+    };
+}
+
+size_classes! {
+    /// Size classes used by default (unless `alloca` feature is enabled): exact sizes up to 32, then multiples of 32 up to 4096,
+    /// then powers of two up to 65536.
+    ///
+    /// For example, for requested 50 item `[T; 64]` will be placed, for 120 items - `[T; 128]`.
+    Linear32 {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4 => 4,
+        5 => 5,
+        6 => 6,
+        7 => 7,
+        8 => 8,
+        9 => 9,
+        10 => 10,
+        11 => 11,
+        12 => 12,
+        13 => 13,
+        14 => 14,
+        15 => 15,
+        16 => 16,
+        17 => 17,
+        18 => 18,
+        19 => 19,
+        20 => 20,
+        21 => 21,
+        22 => 22,
+        23 => 23,
+        24 => 24,
+        25 => 25,
+        26 => 26,
+        27 => 27,
+        28 => 28,
+        29 => 29,
+        30 => 30,
+        31 => 31,
+        32 => 32,
+        33..=64 => 64,
+        65..=96 => 96,
+        97..=128 => 128,
+        129..=160 => 160,
+        161..=192 => 192,
+        193..=224 => 224,
+        225..=256 => 256,
+        257..=288 => 288,
+        289..=320 => 320,
+        321..=352 => 352,
+        353..=384 => 384,
+        385..=416 => 416,
+        417..=448 => 448,
+        449..=480 => 480,
+        481..=512 => 512,
+        513..=544 => 544,
+        545..=576 => 576,
+        577..=608 => 608,
+        609..=640 => 640,
+        641..=672 => 672,
+        673..=704 => 704,
+        705..=736 => 736,
+        737..=768 => 768,
+        769..=800 => 800,
+        801..=832 => 832,
+        833..=864 => 864,
+        865..=896 => 896,
+        897..=928 => 928,
+        929..=960 => 960,
+        961..=992 => 992,
+        993..=1024 => 1024,
+        1025..=1056 => 1056,
+        1057..=1088 => 1088,
+        1089..=1120 => 1120,
+        1121..=1152 => 1152,
+        1153..=1184 => 1184,
+        1185..=1216 => 1216,
+        1217..=1248 => 1248,
+        1249..=1280 => 1280,
+        1281..=1312 => 1312,
+        1313..=1344 => 1344,
+        1345..=1376 => 1376,
+        1377..=1408 => 1408,
+        1409..=1440 => 1440,
+        1441..=1472 => 1472,
+        1473..=1504 => 1504,
+        1505..=1536 => 1536,
+        1537..=1568 => 1568,
+        1569..=1600 => 1600,
+        1601..=1632 => 1632,
+        1633..=1664 => 1664,
+        1665..=1696 => 1696,
+        1697..=1728 => 1728,
+        1729..=1760 => 1760,
+        1761..=1792 => 1792,
+        1793..=1824 => 1824,
+        1825..=1856 => 1856,
+        1857..=1888 => 1888,
+        1889..=1920 => 1920,
+        1921..=1952 => 1952,
+        1953..=1984 => 1984,
+        1985..=2016 => 2016,
+        2017..=2048 => 2048,
+        2049..=2080 => 2080,
+        2081..=2112 => 2112,
+        2113..=2144 => 2144,
+        2145..=2176 => 2176,
+        2177..=2208 => 2208,
+        2209..=2240 => 2240,
+        2241..=2272 => 2272,
+        2273..=2304 => 2304,
+        2305..=2336 => 2336,
+        2337..=2368 => 2368,
+        2369..=2400 => 2400,
+        2401..=2432 => 2432,
+        2433..=2464 => 2464,
+        2465..=2496 => 2496,
+        2497..=2528 => 2528,
+        2529..=2560 => 2560,
+        2561..=2592 => 2592,
+        2593..=2624 => 2624,
+        2625..=2656 => 2656,
+        2657..=2688 => 2688,
+        2689..=2720 => 2720,
+        2721..=2752 => 2752,
+        2753..=2784 => 2784,
+        2785..=2816 => 2816,
+        2817..=2848 => 2848,
+        2849..=2880 => 2880,
+        2881..=2912 => 2912,
+        2913..=2944 => 2944,
+        2945..=2976 => 2976,
+        2977..=3008 => 3008,
+        3009..=3040 => 3040,
+        3041..=3072 => 3072,
+        3073..=3104 => 3104,
+        3105..=3136 => 3136,
+        3137..=3168 => 3168,
+        3169..=3200 => 3200,
+        3201..=3232 => 3232,
+        3233..=3264 => 3264,
+        3265..=3296 => 3296,
+        3297..=3328 => 3328,
+        3329..=3360 => 3360,
+        3361..=3392 => 3392,
+        3393..=3424 => 3424,
+        3425..=3456 => 3456,
+        3457..=3488 => 3488,
+        3489..=3520 => 3520,
+        3521..=3552 => 3552,
+        3553..=3584 => 3584,
+        3585..=3616 => 3616,
+        3617..=3648 => 3648,
+        3649..=3680 => 3680,
+        3681..=3712 => 3712,
+        3713..=3744 => 3744,
+        3745..=3776 => 3776,
+        3777..=3808 => 3808,
+        3809..=3840 => 3840,
+        3841..=3872 => 3872,
+        3873..=3904 => 3904,
+        3905..=3936 => 3936,
+        3937..=3968 => 3968,
+        3969..=4000 => 4000,
+        4001..=4032 => 4032,
+        4033..=4064 => 4064,
+        4065..=4096 => 4096,
+        4097..=8192 => 8192,
+        8193..=16384 => 16384,
+        16385..=32768 => 32768,
+        32769..=65536 => 65536,
+    }
+}
+
+size_classes! {
+    /// Powers of two up to 65536.
+    ///
+    /// It has only 18 classes, so it produces the least code,
+    /// but up to half of placed memory may be unused.
+    PowersOfTwo {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3..=4 => 4,
+        5..=8 => 8,
+        9..=16 => 16,
+        17..=32 => 32,
+        33..=64 => 64,
+        65..=128 => 128,
+        129..=256 => 256,
+        257..=512 => 512,
+        513..=1024 => 1024,
+        1025..=2048 => 2048,
+        2049..=4096 => 4096,
+        4097..=8192 => 8192,
+        8193..=16384 => 16384,
+        16385..=32768 => 32768,
+        32769..=65536 => 65536,
+    }
+}
+
+size_classes! {
+    /// Exact sizes up to 64, then sizes growing by a factor about 1.5 up to 65536.
+    ///
+    /// Small arrays does not waste any memory, which is useful in tight loops.
+    ExactThenGeometric {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4 => 4,
+        5 => 5,
+        6 => 6,
+        7 => 7,
+        8 => 8,
+        9 => 9,
+        10 => 10,
+        11 => 11,
+        12 => 12,
+        13 => 13,
+        14 => 14,
+        15 => 15,
+        16 => 16,
+        17 => 17,
+        18 => 18,
+        19 => 19,
+        20 => 20,
+        21 => 21,
+        22 => 22,
+        23 => 23,
+        24 => 24,
+        25 => 25,
+        26 => 26,
+        27 => 27,
+        28 => 28,
+        29 => 29,
+        30 => 30,
+        31 => 31,
+        32 => 32,
+        33 => 33,
+        34 => 34,
+        35 => 35,
+        36 => 36,
+        37 => 37,
+        38 => 38,
+        39 => 39,
+        40 => 40,
+        41 => 41,
+        42 => 42,
+        43 => 43,
+        44 => 44,
+        45 => 45,
+        46 => 46,
+        47 => 47,
+        48 => 48,
+        49 => 49,
+        50 => 50,
+        51 => 51,
+        52 => 52,
+        53 => 53,
+        54 => 54,
+        55 => 55,
+        56 => 56,
+        57 => 57,
+        58 => 58,
+        59 => 59,
+        60 => 60,
+        61 => 61,
+        62 => 62,
+        63 => 63,
+        64 => 64,
+        65..=96 => 96,
+        97..=128 => 128,
+        129..=192 => 192,
+        193..=256 => 256,
+        257..=384 => 384,
+        385..=512 => 512,
+        513..=768 => 768,
+        769..=1024 => 1024,
+        1025..=1536 => 1536,
+        1537..=2048 => 2048,
+        2049..=3072 => 3072,
+        3073..=4096 => 4096,
+        4097..=6144 => 6144,
+        6145..=8192 => 8192,
+        8193..=12288 => 12288,
+        12289..=16384 => 16384,
+        16385..=24576 => 24576,
+        24577..=32768 => 32768,
+        32769..=49152 => 49152,
+        49153..=65536 => 65536,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_default_class_size_matches_class_len() {
+        for max_len in 0..=70_000 {
+            let max_size = max_default_class_size(max_len);
+            assert!(matches!(DefaultSizeClasses::class_len(max_size), Some(len) if len <= max_len), "{}", max_len);
+            assert!(!matches!(DefaultSizeClasses::class_len(max_size + 1), Some(len) if len <= max_len), "{}", max_len);
+        }
+    }
+}
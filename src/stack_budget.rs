@@ -0,0 +1,210 @@
+use core::{
+    cell::Cell,
+    mem::size_of,
+};
+use crate::guards::UninitializedSliceMemoryGuard;
+use crate::size_classes::{SizeClasses, DefaultSizeClasses};
+use crate::fixed_array::try_inplace_array;
+use crate::alloc_array::alloc_array;
+
+/// Amount of stack memory (in bytes) shared by nested placements.
+///
+/// Each placement made with `try_inplace_array_with_budget` or `inplace_or_alloc_array_with_budget`
+/// takes bytes of placed array from the budget and gives them back when its consumer returns.
+/// When the budget runs out, arrays are not placed on the stack anymore.
+///
+/// With `std` feature enabled each thread also has its own budget used by all placements
+/// (see `set_thread_stack_budget`).
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array_with_budget, StackBudget, UninitializedSliceMemoryGuard};
+///
+/// let budget = StackBudget::new(10_000);
+/// inplace_or_alloc_array_with_budget(&budget, 4096, |outer: UninitializedSliceMemoryGuard<u8>| {
+///     // Placed on the stack
+///     assert_eq!(outer.len(), 4096);
+///     assert_eq!(budget.remaining(), 10_000 - 4096);
+///     inplace_or_alloc_array_with_budget(&budget, 4096, |inner: UninitializedSliceMemoryGuard<u8>| {
+///         assert_eq!(inner.len(), 4096);
+///         assert_eq!(budget.remaining(), 10_000 - 2 * 4096);
+///         inplace_or_alloc_array_with_budget(&budget, 4096, |innermost: UninitializedSliceMemoryGuard<u8>| {
+///             // The budget is run out, so the array is allocated in the heap
+///             assert_eq!(innermost.len(), 4096);
+///         });
+///     });
+/// });
+/// assert_eq!(budget.remaining(), 10_000);
+/// ```
+#[derive(Debug)]
+pub struct StackBudget {
+    limit: Cell<usize>,
+    used: Cell<usize>,
+}
+
+impl StackBudget {
+    /// Create new budget of `bytes` bytes.
+    #[inline]
+    pub const fn new(bytes: usize) -> Self {
+        Self { limit: Cell::new(bytes), used: Cell::new(0) }
+    }
+
+    /// Get amount of bytes left in the budget.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.limit.get().saturating_sub(self.used.get())
+    }
+
+    /// Set amount of bytes left in the budget.
+    ///
+    /// Bytes taken by running placements will be added to it when their consumers return.
+    #[inline]
+    pub fn set_remaining(&self, bytes: usize) {
+        self.limit.set(self.used.get().saturating_add(bytes))
+    }
+
+    /// Set amount of bytes of the budget including bytes taken by running placements.
+    ///
+    /// Unlike `set_remaining`, the budget does not grow when running placements give their bytes back.
+    #[inline]
+    pub fn set_limit(&self, bytes: usize) {
+        self.limit.set(bytes)
+    }
+
+    /// Takes `bytes` from the budget if there is enough of them.
+    /// Returned reservation gives them back on drop.
+    #[inline]
+    fn reserve(&self, bytes: usize) -> Option<BudgetReservation<'_>> {
+        self.take(bytes)?;
+        Some(BudgetReservation { budget: self, bytes })
+    }
+
+    #[inline]
+    fn take(&self, bytes: usize) -> Option<()> {
+        if bytes > self.remaining() {
+            return None;
+        }
+        self.used.set(self.used.get() + bytes);
+        Some(())
+    }
+
+    #[inline]
+    fn give_back(&self, bytes: usize) {
+        self.used.set(self.used.get() - bytes)
+    }
+}
+
+struct BudgetReservation<'a> {
+    budget: &'a StackBudget,
+    bytes: usize,
+}
+
+impl<'a> Drop for BudgetReservation<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.budget.give_back(self.bytes);
+    }
+}
+
+/// `try_inplace_array_with_budget` works like `try_inplace_array`
+/// but also takes bytes of placed array from given `budget` while `consumer` runs.
+///
+/// If there is not enough bytes in the `budget` then `Err(consumer)` will be returned.
+pub fn try_inplace_array_with_budget<T, R, Consumer>(budget: &StackBudget, size: usize, consumer: Consumer) -> Result<R, Consumer>
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    let bytes = match DefaultSizeClasses::class_len(size) {
+        Some(len) => len.saturating_mul(size_of::<T>()),
+        None => return Err(consumer),
+    };
+    match budget.reserve(bytes) {
+        Some(_reservation) => try_inplace_array(size, consumer),
+        None => Err(consumer),
+    }
+}
+
+/// `inplace_or_alloc_array_with_budget` works like `inplace_or_alloc_array`
+/// but also takes bytes of placed array from given `budget` while `consumer` runs.
+///
+/// If there is not enough bytes in the `budget` then the vector will be allocated in the heap.
+pub fn inplace_or_alloc_array_with_budget<T, R, Consumer>(budget: &StackBudget, size: usize, consumer: Consumer) -> R
+    where Consumer: FnOnce(UninitializedSliceMemoryGuard<T>) -> R
+{
+    match try_inplace_array_with_budget(budget, size, consumer) {
+        Ok(result) => result,
+        Err(consumer) => alloc_array(size, consumer),
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static THREAD_STACK_BUDGET: StackBudget = const { StackBudget::new(usize::MAX) };
+}
+
+/// Set amount of stack memory (in bytes) shared by all placements on the current thread.
+///
+/// Each placement takes bytes of placed array from this budget and gives them back when its
+/// consumer returns, so nested placements can not take more than `bytes` in total.
+/// When the budget runs out, `try_inplace_array` fails and `inplace_or_alloc_array` allocates arrays in the heap.
+///
+/// Bytes taken by running placements count against the new budget, so it can be set inside of consumers too.
+///
+/// The budget is unlimited by default.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array, set_thread_stack_budget, thread_stack_budget};
+///
+/// set_thread_stack_budget(6000);
+/// inplace_or_alloc_array(4096, |outer| {
+///     assert_eq!(outer.len(), 4096);
+///     assert_eq!(thread_stack_budget(), 6000 - 4096);
+///     inplace_or_alloc_array(4096, |inner| {
+///         assert_eq!(inner.len(), 4096);
+///         # let _: inplace_it::UninitializedSliceMemoryGuard<u8> = inner;
+///     });
+///     # let _: inplace_it::UninitializedSliceMemoryGuard<u8> = outer;
+/// });
+/// assert_eq!(thread_stack_budget(), 6000);
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+pub fn set_thread_stack_budget(bytes: usize) {
+    THREAD_STACK_BUDGET.with(|budget| budget.set_limit(bytes))
+}
+
+/// Get amount of bytes left in the current thread's stack budget.
+///
+/// See `set_thread_stack_budget`.
+#[cfg(feature = "std")]
+#[inline]
+pub fn thread_stack_budget() -> usize {
+    THREAD_STACK_BUDGET.try_with(StackBudget::remaining).unwrap_or(usize::MAX)
+}
+
+/// Bytes taken from the current thread's stack budget. Gives them back on drop.
+#[cfg(feature = "std")]
+pub(crate) struct ThreadBudgetReservation {
+    bytes: usize,
+}
+
+#[cfg(feature = "std")]
+impl ThreadBudgetReservation {
+    /// Takes `bytes` from the current thread's stack budget if there is enough of them.
+    #[inline]
+    pub(crate) fn new(bytes: usize) -> Option<Self> {
+        THREAD_STACK_BUDGET.try_with(|budget| budget.take(bytes).map(|()| ThreadBudgetReservation { bytes }))
+            .unwrap_or(Some(ThreadBudgetReservation { bytes: 0 }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for ThreadBudgetReservation {
+    #[inline]
+    fn drop(&mut self) {
+        let bytes = self.bytes;
+        let _ = THREAD_STACK_BUDGET.try_with(|budget| budget.give_back(bytes));
+    }
+}
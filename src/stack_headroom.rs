@@ -0,0 +1,226 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    io,
+    thread::{Builder, JoinHandle},
+};
+
+/// Default amount of stack memory (in bytes) which should stay free after placing an array.
+pub const DEFAULT_STACK_SAFETY_MARGIN: usize = 64 * 1024;
+
+static STACK_SAFETY_MARGIN: AtomicUsize = AtomicUsize::new(DEFAULT_STACK_SAFETY_MARGIN);
+
+/// Set amount of stack memory (in bytes) which should stay free after placing an array.
+///
+/// If placing an array would leave less than `bytes` of the current thread's stack,
+/// `try_inplace_array` fails and `inplace_or_alloc_array` allocates the array in the heap.
+/// The margin is shared by all threads.
+#[inline]
+pub fn set_stack_safety_margin(bytes: usize) {
+    STACK_SAFETY_MARGIN.store(bytes, Ordering::Relaxed);
+}
+
+/// Get amount of stack memory (in bytes) which should stay free after placing an array.
+#[inline]
+pub fn stack_safety_margin() -> usize {
+    STACK_SAFETY_MARGIN.load(Ordering::Relaxed)
+}
+
+/// Get amount of stack memory (in bytes) left below the current stack pointer.
+///
+/// Stack bounds are known only on Linux and only for the main thread
+/// (found from `/proc/self/maps` and `/proc/self/limits`)
+/// and for threads spawned with `spawn_with_stack_bounds`.
+/// `None` will be returned for other threads.
+///
+/// The main thread's stack bounds are looked up once per process, so the first call
+/// (on any thread) reads these files and allocates. Later calls do not allocate.
+/// If the stack size is unlimited, only the part of the main thread's stack which is already mapped is counted.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{remaining_stack, spawn_with_stack_bounds};
+///
+/// let remaining = spawn_with_stack_bounds(std::thread::Builder::new().stack_size(1024 * 1024), || {
+///     remaining_stack()
+/// }).unwrap().join().unwrap();
+/// # #[cfg(target_os = "linux")]
+/// assert!(remaining.unwrap() < 1024 * 1024);
+/// ```
+#[inline]
+pub fn remaining_stack() -> Option<usize> {
+    let stack_pointer = approximate_stack_pointer();
+    let (bottom, top) = imp::current_stack_bounds()?;
+    if (bottom..top).contains(&stack_pointer) {
+        Some(stack_pointer - bottom)
+    } else {
+        None
+    }
+}
+
+/// Spawns a new thread with given `builder` and records its stack bounds,
+/// so `remaining_stack` is known in it.
+///
+/// # Examples
+///
+/// ```rust
+/// use inplace_it::{inplace_or_alloc_array, spawn_with_stack_bounds};
+///
+/// let handle = spawn_with_stack_bounds(std::thread::Builder::new(), || {
+///     inplace_or_alloc_array(1000, |guard| guard.slice(..1000).init(|i| i as u32).iter().sum::<u32>())
+/// }).unwrap();
+/// assert_eq!(handle.join().unwrap(), 999 * 1000 / 2);
+/// ```
+pub fn spawn_with_stack_bounds<F, T>(builder: Builder, f: F) -> io::Result<JoinHandle<T>>
+    where F: FnOnce() -> T + Send + 'static,
+          T: Send + 'static,
+{
+    builder.spawn(move || {
+        imp::record_stack_bounds();
+        f()
+    })
+}
+
+/// Checks that placing `bytes` bytes on the stack leaves at least `stack_safety_margin()` bytes free.
+/// Always `true` if the current thread's stack bounds are unknown.
+#[inline]
+pub(crate) fn fits_stack_headroom(bytes: usize) -> bool {
+    match remaining_stack() {
+        Some(remaining) => remaining.saturating_sub(bytes) >= stack_safety_margin(),
+        None => true,
+    }
+}
+
+#[inline(always)]
+fn approximate_stack_pointer() -> usize {
+    let marker = 0u8;
+    &marker as *const u8 as usize
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::approximate_stack_pointer;
+    use core::cell::Cell;
+    use std::{fs, sync::OnceLock, thread_local};
+
+    thread_local! {
+        /// `None` if bounds were not looked up yet, `Some(None)` if they are unknown.
+        static STACK_BOUNDS: Cell<Option<Option<(usize, usize)>>> = const { Cell::new(None) };
+    }
+
+    pub(super) fn current_stack_bounds() -> Option<(usize, usize)> {
+        STACK_BOUNDS.try_with(|bounds| {
+            match bounds.get() {
+                Some(known) => known,
+                None => {
+                    // Only the main thread runs on the main thread's stack
+                    let stack_pointer = approximate_stack_pointer();
+                    let found = main_thread_stack_bounds()
+                        .filter(|(bottom, top)| (*bottom..*top).contains(&stack_pointer));
+                    bounds.set(Some(found));
+                    found
+                }
+            }
+        }).ok().flatten()
+    }
+
+    /// Bounds are taken from `pthread_getattr_np` on glibc (they do not include the guard page)
+    /// or from the mapping of the current stack pointer in `/proc/self/maps` otherwise.
+    pub(super) fn record_stack_bounds() {
+        let stack_pointer = approximate_stack_pointer();
+        let found = pthread::current_thread_stack_bounds()
+            .filter(|(bottom, top)| (*bottom..*top).contains(&stack_pointer))
+            .or_else(|| find_mapping(|start, end, _| (start..end).contains(&stack_pointer)));
+        let _ = STACK_BOUNDS.try_with(|bounds| bounds.set(Some(found)));
+    }
+
+    /// Bounds of the main thread's stack. They are looked up once per process.
+    fn main_thread_stack_bounds() -> Option<(usize, usize)> {
+        static MAIN_THREAD_STACK_BOUNDS: OnceLock<Option<(usize, usize)>> = OnceLock::new();
+        *MAIN_THREAD_STACK_BOUNDS.get_or_init(find_main_thread_stack_bounds)
+    }
+
+    /// The main thread's stack is the `[stack]` mapping which grows down up to `RLIMIT_STACK` bytes.
+    /// If the limit is unknown or unlimited, the mapping's current bounds are used.
+    fn find_main_thread_stack_bounds() -> Option<(usize, usize)> {
+        let (start, end) = find_mapping(|_, _, path| path == Some("[stack]"))?;
+        match stack_size_limit() {
+            Some(limit) => Some((end.saturating_sub(limit), end)),
+            None => Some((start, end)),
+        }
+    }
+
+    /// Finds the first mapping in `/proc/self/maps` for which `matches(start, end, path)` is `true`.
+    fn find_mapping(matches: impl Fn(usize, usize, Option<&str>) -> bool) -> Option<(usize, usize)> {
+        let maps = fs::read_to_string("/proc/self/maps").ok()?;
+        maps.lines().find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut range = fields.next()?.split('-');
+            let start = usize::from_str_radix(range.next()?, 16).ok()?;
+            let end = usize::from_str_radix(range.next()?, 16).ok()?;
+            if matches(start, end, fields.nth(4)) {
+                Some((start, end))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Reads the soft limit of "Max stack size" from `/proc/self/limits`.
+    /// `None` will be returned if it's unlimited.
+    fn stack_size_limit() -> Option<usize> {
+        let limits = fs::read_to_string("/proc/self/limits").ok()?;
+        let line = limits.lines().find(|line| line.starts_with("Max stack size"))?;
+        line["Max stack size".len()..].split_whitespace().next()?.parse().ok()
+    }
+
+    #[cfg(target_env = "gnu")]
+    mod pthread {
+        use core::ffi::{c_int, c_void};
+
+        /// Storage of `pthread_attr_t`. It's larger and aligned more than glibc's one on all targets.
+        #[repr(C, align(16))]
+        struct PthreadAttr([u8; 128]);
+
+        extern "C" {
+            fn pthread_self() -> usize;
+            fn pthread_getattr_np(thread: usize, attr: *mut PthreadAttr) -> c_int;
+            fn pthread_attr_getstack(attr: *const PthreadAttr, stack_addr: *mut *mut c_void, stack_size: *mut usize) -> c_int;
+            fn pthread_attr_destroy(attr: *mut PthreadAttr) -> c_int;
+        }
+
+        pub(super) fn current_thread_stack_bounds() -> Option<(usize, usize)> {
+            let mut attr = PthreadAttr([0; 128]);
+            let mut stack_addr = core::ptr::null_mut();
+            let mut stack_size = 0;
+            unsafe {
+                if pthread_getattr_np(pthread_self(), &mut attr) != 0 {
+                    return None;
+                }
+                let result = pthread_attr_getstack(&attr, &mut stack_addr, &mut stack_size);
+                pthread_attr_destroy(&mut attr);
+                if result != 0 {
+                    return None;
+                }
+            }
+            let bottom = stack_addr as usize;
+            Some((bottom, bottom.checked_add(stack_size)?))
+        }
+    }
+
+    #[cfg(not(target_env = "gnu"))]
+    mod pthread {
+        pub(super) fn current_thread_stack_bounds() -> Option<(usize, usize)> {
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    pub(super) fn current_stack_bounds() -> Option<(usize, usize)> {
+        None
+    }
+
+    pub(super) fn record_stack_bounds() {}
+}
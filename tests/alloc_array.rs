@@ -7,8 +7,189 @@ fn inplace_or_alloc_from_iter_works_fine() {
         let result = ::inplace_it::inplace_or_alloc_from_iter(0..count, |mem| {
             assert_eq!(mem.len(), count);
             assert!(mem.iter().cloned().eq(0..count));
-            return mem.len() * 2;
+            mem.len() * 2
         });
         assert_eq!(result, count * 2);
     }
 }
+
+#[test]
+fn inplace_or_alloc_array_moves_large_items_to_heap() {
+    // 16 items of 1 KiB are placed on the stack, but 17 and more are not
+    for count in 0..=16 {
+        let placed = ::inplace_it::try_inplace_array(count, |mem: ::inplace_it::UninitializedSliceMemoryGuard<[u8; 1024]>| {
+            mem.len()
+        });
+        assert!(placed.is_ok());
+    }
+    for count in 17..64 {
+        let placed = ::inplace_it::try_inplace_array(count, |mem: ::inplace_it::UninitializedSliceMemoryGuard<[u8; 1024]>| {
+            mem.len()
+        });
+        assert!(placed.is_err());
+        // Vector's size is always equal to requested
+        let len = ::inplace_it::inplace_or_alloc_array(count, |mem: ::inplace_it::UninitializedSliceMemoryGuard<[u8; 1024]>| {
+            mem.len()
+        });
+        assert_eq!(len, count);
+    }
+}
+
+#[test]
+fn inplace_or_alloc_array_with_limit_places_up_to_limit() {
+    const LIMIT: usize = 256 * 1024;
+    for count in (0..=65536).step_by(4096) {
+        let placed = ::inplace_it::try_inplace_array_with_limit::<u32, LIMIT, _, _>(count, |mem| {
+            mem.len()
+        });
+        assert!(placed.ok().unwrap() >= count);
+    }
+    let placed = ::inplace_it::try_inplace_array_with_limit::<u32, LIMIT, _, _>(65537, |mem| {
+        mem.len()
+    });
+    assert!(placed.is_err());
+    let len = ::inplace_it::inplace_or_alloc_array_with_limit::<u32, LIMIT, _, _>(65537, |mem| {
+        mem.len()
+    });
+    assert_eq!(len, 65537);
+}
+
+#[test]
+fn alloc_array_in_uses_given_allocator() {
+    use core::{alloc::Layout, cell::RefCell, ptr::NonNull};
+    use inplace_it::{Global, ScratchAllocator, UninitializedSliceMemoryGuard};
+
+    #[derive(Default)]
+    struct Recording {
+        allocated: RefCell<Vec<Layout>>,
+        deallocated: RefCell<Vec<Layout>>,
+    }
+
+    unsafe impl ScratchAllocator for Recording {
+        fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+            self.allocated.borrow_mut().push(layout);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.deallocated.borrow_mut().push(layout);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    let allocator = Recording::default();
+    // Placed on the stack
+    let len = ::inplace_it::inplace_or_alloc_array_in(&allocator, 128, |mem: UninitializedSliceMemoryGuard<u64>| mem.len());
+    assert_eq!(len, 128);
+    assert!(allocator.allocated.borrow().is_empty());
+    // Allocated with the allocator
+    let sum = ::inplace_it::inplace_or_alloc_array_in(&allocator, 10_000, |mem: UninitializedSliceMemoryGuard<u64>| {
+        mem.init(|i| i as u64).iter().sum::<u64>()
+    });
+    assert_eq!(sum, 9_999 * 10_000 / 2);
+    // Zero-sized arrays are not allocated
+    ::inplace_it::alloc_array_in(&allocator, 0, |mem: UninitializedSliceMemoryGuard<u64>| assert_eq!(mem.len(), 0));
+    ::inplace_it::alloc_array_in(&allocator, 10, |mem: UninitializedSliceMemoryGuard<()>| assert_eq!(mem.len(), 10));
+
+    let expected = vec![Layout::array::<u64>(10_000).unwrap()];
+    assert_eq!(*allocator.allocated.borrow(), expected);
+    assert_eq!(*allocator.deallocated.borrow(), expected);
+}
+
+#[test]
+fn try_alloc_array_reports_allocation_failure() {
+    use core::{alloc::Layout, ptr::NonNull};
+    use inplace_it::{AllocArrayError, ScratchAllocator, UninitializedSliceMemoryGuard};
+
+    struct Failing;
+
+    unsafe impl ScratchAllocator for Failing {
+        fn allocate(&self, _layout: Layout) -> Option<NonNull<u8>> {
+            None
+        }
+
+        unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+            unreachable!("Nothing is allocated")
+        }
+    }
+
+    let result = ::inplace_it::try_alloc_array_in(&Failing, 1000, |mem: UninitializedSliceMemoryGuard<u32>| mem.len());
+    match result {
+        Err((error, consumer)) => {
+            assert_eq!(error, AllocArrayError::AllocFailed { layout: Layout::array::<u32>(1000).unwrap() });
+            assert_eq!(::inplace_it::try_alloc_array(1000, consumer).ok(), Some(1000));
+        }
+        Ok(_) => unreachable!("Failing allocator cannot allocate"),
+    }
+
+    let result = ::inplace_it::try_alloc_array_in(&Failing, usize::MAX, |mem: UninitializedSliceMemoryGuard<u32>| mem.len());
+    assert!(matches!(result, Err((AllocArrayError::CapacityOverflow, _))));
+    // Zero-sized arrays are never allocated, so they never fail
+    let result = ::inplace_it::try_alloc_array_in(&Failing, 0, |mem: UninitializedSliceMemoryGuard<u32>| mem.len());
+    assert_eq!(result.ok(), Some(0));
+}
+
+#[test]
+fn inplace_or_alloc_from_iter_handles_filtered_and_unbounded_iterators() {
+    // Few items of filtered iterator, and more items than placed on the stack
+    for &step in [1, 7, 1000, 300_000].iter() {
+        let expected = (0..1_000_000usize).filter(|i| i % step == 0).collect::<Vec<_>>();
+        let result = ::inplace_it::inplace_or_alloc_from_iter((0..1_000_000).filter(|i| i % step == 0), |mem| {
+            assert_eq!(mem, &*expected);
+            mem.len()
+        });
+        assert_eq!(result, expected.len());
+    }
+
+    // Unbounded iterator
+    for &count in [0, 3, 256, 257, 5000].iter() {
+        let mut counter = 0usize;
+        let iter = std::iter::from_fn(|| {
+            counter += 1;
+            if counter <= count { Some(counter) } else { None }
+        });
+        let len = ::inplace_it::inplace_or_alloc_from_iter_with_capacity(iter, 10, |mem| {
+            assert!(mem.iter().cloned().eq(1..=count));
+            mem.len()
+        });
+        assert_eq!(len, count);
+    }
+
+    // Items too large for the stack
+    let len = ::inplace_it::inplace_or_alloc_from_iter((0..100).map(|_| [0u8; 32 * 1024]), |mem| mem.len());
+    assert_eq!(len, 100);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn inplace_or_alloc_from_iter_places_filtered_iterator_on_stack() {
+    use inplace_it::{set_thread_stack_budget, thread_stack_budget};
+
+    set_thread_stack_budget(1024 * 1024);
+    // Default capacity of 256 items is placed and reserved from the thread's budget
+    ::inplace_it::inplace_or_alloc_from_iter((0..1_000_000u32).filter(|i| i % 300_000 == 0), |mem| {
+        assert_eq!(mem, &[0, 300_000, 600_000, 900_000]);
+        assert_eq!(thread_stack_budget(), 1024 * 1024 - 256 * 4);
+    });
+    // Unbounded iterator is placed by given capacity
+    let mut taken = 0;
+    let unbounded = std::iter::repeat(1u64).take_while(move |_| {
+        taken += 1;
+        taken <= 3
+    });
+    ::inplace_it::inplace_or_alloc_from_iter_with_capacity(unbounded, 1024, |mem| {
+        assert_eq!(mem, &[1, 1, 1]);
+        assert_eq!(thread_stack_budget(), 1024 * 1024 - 1024 * 8);
+    });
+    // Exact size iterator is placed by its length
+    ::inplace_it::inplace_or_alloc_from_iter(0..128u64, |mem| {
+        assert_eq!(mem.len(), 128);
+        assert_eq!(thread_stack_budget(), 1024 * 1024 - 128 * 8);
+    });
+    // Exact size iterator too large for the stack is collected into `Vec` right away
+    ::inplace_it::inplace_or_alloc_from_iter(0..100_000u64, |mem| {
+        assert_eq!(mem.len(), 100_000);
+        assert_eq!(thread_stack_budget(), 1024 * 1024);
+    });
+    set_thread_stack_budget(usize::MAX);
+}
@@ -9,7 +9,7 @@ struct DropCounter {
 impl DropCounter {
     fn with_current<F: FnOnce(&DropCounter) -> R, R>(f: F) -> R {
         thread_local!(
-            static COUNTER: DropCounter = DropCounter {count: Cell::new(0)};
+            static COUNTER: DropCounter = const { DropCounter {count: Cell::new(0)} };
         );
         COUNTER.with(f)
     }
@@ -30,7 +30,7 @@ impl DropCounter {
     }
 }
 
-struct DropCounterTrigger(u8 /* One byte to avoid zero-sized types optimizations */);
+struct DropCounterTrigger(#[allow(dead_code)] u8 /* One byte to avoid zero-sized types optimizations */);
 
 impl DropCounterTrigger {
     fn new() -> Self {
@@ -46,6 +46,7 @@ impl Drop for DropCounterTrigger {
 }
 
 #[test]
+#[allow(clippy::drop_non_drop)]
 fn maybe_uninit_works_as_expected() {
     DropCounter::clear();
     drop(MaybeUninit::<DropCounterTrigger>::uninit());
@@ -98,3 +99,261 @@ fn alloc_array_should_correctly_drop_values() {
         assert_eq!(DropCounter::get(), i);
     }
 }
+
+fn panics<R>(f: impl FnOnce() -> R) -> bool {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).is_err()
+}
+
+#[test]
+fn init_should_drop_initialized_prefix_on_panic() {
+    for panic_at in [0, 1, 17, 99].iter().cloned() {
+        DropCounter::clear();
+        assert!(panics(|| {
+            inplace_or_alloc_array(100, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+                guard.init(|index| {
+                    if index == panic_at {
+                        panic!("Initializer panics at {}", index);
+                    }
+                    DropCounterTrigger::new()
+                });
+            })
+        }));
+        assert_eq!(DropCounter::get(), panic_at);
+    }
+}
+
+#[test]
+fn init_with_dyn_iter_should_drop_collected_items_on_panic() {
+    // Panics while filling placed memory and while collecting into `Vec`
+    for panic_at in [0, 5, 31, 32, 50].iter().cloned() {
+        DropCounter::clear();
+        assert!(panics(|| {
+            try_inplace_array(32, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+                let iter = (0..100).map(|index| {
+                    if index == panic_at {
+                        panic!("Iterator panics at {}", index);
+                    }
+                    DropCounterTrigger::new()
+                });
+                let _ = guard.init_with_dyn_iter(iter);
+            })
+        }));
+        assert_eq!(DropCounter::get(), panic_at);
+    }
+}
+
+#[test]
+fn slice_memory_guard_should_drop_rest_items_if_one_panics() {
+    struct PanicOnDrop(bool);
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            DropCounter::inc();
+            if self.0 {
+                panic!("Drop panics");
+            }
+        }
+    }
+
+    DropCounter::clear();
+    assert!(panics(|| {
+        inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<PanicOnDrop>| {
+            guard.init(|index| PanicOnDrop(index == 3));
+        })
+    }));
+    assert_eq!(DropCounter::get(), 10);
+}
+
+#[test]
+fn try_init_should_drop_initialized_prefix_on_error() {
+    for error_at in [0, 1, 17, 99].iter().cloned() {
+        DropCounter::clear();
+        inplace_or_alloc_array(100, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+            let guard = guard.slice(..100);
+            let result = guard.try_init(|index| {
+                if index == error_at {
+                    Err(index)
+                } else {
+                    Ok(DropCounterTrigger::new())
+                }
+            });
+            let (error, guard) = result.err().unwrap();
+            assert_eq!(error, error_at);
+            assert_eq!(DropCounter::get(), error_at);
+            // Memory can be used again
+            assert_eq!(guard.len(), 100);
+            assert!(guard.try_init(|_| Ok::<_, ()>(DropCounterTrigger::new())).is_ok());
+        });
+        assert_eq!(DropCounter::get(), error_at + 100);
+    }
+}
+
+#[test]
+fn try_init_with_dyn_iter_should_drop_collected_items_on_error() {
+    // Errors while filling placed memory and while collecting into `Vec`
+    for error_at in [0, 5, 31, 32, 50].iter().cloned() {
+        DropCounter::clear();
+        try_inplace_array(32, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+            let iter = (0..100).map(|index| {
+                if index == error_at {
+                    Err(index)
+                } else {
+                    Ok(DropCounterTrigger::new())
+                }
+            });
+            let (error, guard) = guard.try_init_with_dyn_iter(iter).err().unwrap();
+            assert_eq!(error, error_at);
+            assert_eq!(DropCounter::get(), error_at);
+            assert_eq!(guard.len(), 32);
+        }).map_err(|_| "Cannot inplace array of 32 size").unwrap();
+    }
+
+    DropCounter::clear();
+    try_inplace_array(32, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let iter = (0..50).map(|_| Ok::<_, ()>(DropCounterTrigger::new()));
+        let vec = guard.try_init_with_dyn_iter(iter).ok().unwrap().err().unwrap();
+        assert_eq!(vec.len(), 50);
+    }).map_err(|_| "Cannot inplace array of 32 size").unwrap();
+    assert_eq!(DropCounter::get(), 50);
+}
+
+#[test]
+fn init_with_raw_should_own_only_initialized_prefix() {
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let guard = guard.slice(..10);
+        let (initialized, rest) = unsafe {
+            guard.init_with_raw(|ptr, len| {
+                assert_eq!(len, 10);
+                for index in 0..4 {
+                    ptr.add(index).write(DropCounterTrigger::new());
+                }
+                4
+            })
+        };
+        assert_eq!(initialized.len(), 4);
+        assert_eq!(rest.len(), 6);
+        // The tail is available for initialization
+        let tail = rest.init(|_| DropCounterTrigger::new());
+        drop(initialized);
+        assert_eq!(DropCounter::get(), 4);
+        drop(tail);
+        assert_eq!(DropCounter::get(), 10);
+    });
+}
+
+#[test]
+fn init_with_raw_should_panic_on_too_large_count() {
+    assert!(panics(|| {
+        try_inplace_array(8, |guard: UninitializedSliceMemoryGuard<u8>| {
+            let _ = unsafe { guard.init_with_raw(|_, len| len + 1) };
+        }).map_err(|_| "Cannot inplace array of 8 size").unwrap();
+    }));
+}
+
+#[test]
+fn into_iter_should_move_elements_and_drop_the_rest() {
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let mut guard = guard.slice(..10);
+        {
+            let mut iter = guard.borrow().init(|_| DropCounterTrigger::new()).into_iter();
+            assert_eq!(iter.len(), 10);
+            let first = iter.next().unwrap();
+            let last = iter.next_back().unwrap();
+            assert_eq!(iter.len(), 8);
+            assert_eq!(DropCounter::get(), 0);
+            drop(first);
+            drop(last);
+            assert_eq!(DropCounter::get(), 2);
+            // The rest elements are dropped with the iterator
+        }
+        assert_eq!(DropCounter::get(), 10);
+
+        let mut iter = guard.init(|_| DropCounterTrigger::new()).into_iter();
+        let moved = iter.by_ref().take(3).collect::<Vec<_>>();
+        let guard = iter.into_uninit();
+        assert_eq!(DropCounter::get(), 17);
+        drop(moved);
+        assert_eq!(DropCounter::get(), 20);
+        // Memory can be used again
+        let guard = guard.init(|_| DropCounterTrigger::new());
+        assert_eq!(guard.into_iter().count(), 10);
+        assert_eq!(DropCounter::get(), 30);
+    });
+}
+
+#[test]
+fn clear_and_truncate_should_drop_elements_and_return_memory() {
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let mut guard = guard.slice(..10);
+        for round in 1..=3 {
+            guard = guard.init(|_| DropCounterTrigger::new()).clear();
+            assert_eq!(guard.len(), 10);
+            assert_eq!(DropCounter::get(), round * 10);
+        }
+
+        DropCounter::clear();
+        let (head, tail) = guard.init(|_| DropCounterTrigger::new()).truncate(7);
+        assert_eq!((head.len(), tail.len()), (7, 3));
+        assert_eq!(DropCounter::get(), 3);
+        let tail = tail.init(|_| DropCounterTrigger::new());
+        // Too large length keeps all elements
+        let (head, empty) = head.truncate(100);
+        assert_eq!((head.len(), empty.len()), (7, 0));
+        drop(head);
+        assert_eq!(DropCounter::get(), 10);
+        drop(tail);
+        assert_eq!(DropCounter::get(), 13);
+    });
+}
+
+#[test]
+fn split_guards_should_drop_their_own_parts() {
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let (left, right) = guard.slice(..10).split_at(4);
+        assert_eq!((left.len(), right.len()), (4, 6));
+        let right = right.init(|_| DropCounterTrigger::new());
+        let left = left.init(|_| DropCounterTrigger::new());
+        let (first, second) = right.split_at(1);
+        drop(second);
+        assert_eq!(DropCounter::get(), 5);
+        drop(first);
+        drop(left);
+        assert_eq!(DropCounter::get(), 10);
+    });
+}
+
+#[test]
+fn chunks_exact_should_drop_every_element_once() {
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let mut guard = guard.slice(..10);
+        let lens = guard.borrow().into_chunks_exact(3).map(|chunk| chunk.len()).collect::<Vec<_>>();
+        assert_eq!(lens, vec![3, 3, 3]);
+        assert_eq!(guard.borrow().into_chunks_exact(3).into_remainder().len(), 1);
+
+        let mut chunks = guard.init(|_| DropCounterTrigger::new()).into_chunks_exact(3);
+        assert_eq!(chunks.len(), 3);
+        let last = chunks.next_back().unwrap();
+        drop(chunks.next());
+        assert_eq!(DropCounter::get(), 3);
+        // The rest chunk is dropped with the iterator
+        let remainder = chunks.into_remainder();
+        assert_eq!(DropCounter::get(), 6);
+        assert_eq!(remainder.len(), 1);
+        drop(remainder);
+        drop(last);
+        assert_eq!(DropCounter::get(), 10);
+    });
+
+    DropCounter::clear();
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<DropCounterTrigger>| {
+        let mut chunks = guard.slice(..10).init(|_| DropCounterTrigger::new()).into_chunks_exact(4);
+        drop(chunks.next());
+        drop(chunks);
+        assert_eq!(DropCounter::get(), 10);
+    });
+}
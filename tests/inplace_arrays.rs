@@ -0,0 +1,74 @@
+use inplace_it::*;
+
+#[repr(align(64))]
+struct OverAligned(#[allow(dead_code)] u8);
+
+#[test]
+fn inplace_or_alloc_arrays_places_arrays_of_different_types() {
+    for &count in [0, 1, 7, 100, 5000].iter() {
+        let (sum, max) = inplace_or_alloc_arrays(
+            (count, count * 2, 3),
+            |(bytes, wide, flags): (UninitializedSliceMemoryGuard<u8>, UninitializedSliceMemoryGuard<u64>, UninitializedSliceMemoryGuard<bool>)| {
+                assert_eq!((bytes.len(), wide.len(), flags.len()), (count, count * 2, 3));
+                let bytes = bytes.init(|index| index as u8);
+                let wide = wide.init(|index| index as u64);
+                let flags = flags.init(|_| true);
+                // Arrays do not overlap
+                assert!(bytes.iter().enumerate().all(|(index, byte)| *byte == index as u8));
+                assert!(flags.iter().all(|flag| *flag));
+                (wide.iter().sum::<u64>(), wide.iter().max().cloned())
+            },
+        );
+        let wide_count = count as u64 * 2;
+        assert_eq!(sum, wide_count * wide_count.saturating_sub(1) / 2);
+        assert_eq!(max, wide_count.checked_sub(1));
+    }
+}
+
+#[test]
+fn inplace_or_alloc_arrays_aligns_every_array() {
+    for &count in [1, 3, 1000].iter() {
+        inplace_or_alloc_arrays(
+            (count, count, count, count),
+            |(a, b, c, d): (UninitializedSliceMemoryGuard<u8>, UninitializedSliceMemoryGuard<OverAligned>, UninitializedSliceMemoryGuard<()>, UninitializedSliceMemoryGuard<u16>)| {
+                assert_eq!((a.len(), b.len(), c.len(), d.len()), (count, count, count, count));
+                let b = b.init(|_| OverAligned(1));
+                let d = d.init(|index| index as u16);
+                assert_eq!(b.as_ptr() as usize % 64, 0);
+                assert_eq!(d.as_ptr() as usize % 2, 0);
+            },
+        );
+    }
+}
+
+#[test]
+#[allow(clippy::type_complexity)]
+fn inplace_or_alloc_arrays_supports_up_to_eight_arrays() {
+    let len = inplace_or_alloc_arrays((1, 2, 3, 4, 5, 6, 7, 8), |(a, b, c, d, e, f, g, h): (
+        UninitializedSliceMemoryGuard<u8>,
+        UninitializedSliceMemoryGuard<u16>,
+        UninitializedSliceMemoryGuard<u32>,
+        UninitializedSliceMemoryGuard<u64>,
+        UninitializedSliceMemoryGuard<u128>,
+        UninitializedSliceMemoryGuard<f32>,
+        UninitializedSliceMemoryGuard<f64>,
+        UninitializedSliceMemoryGuard<char>,
+    )| {
+        a.len() + b.len() + c.len() + d.len() + e.len() + f.len() + g.len() + h.len()
+    });
+    assert_eq!(len, 36);
+
+    let len = inplace_or_alloc_arrays((10,), |(a,): (UninitializedSliceMemoryGuard<u8>,)| a.len());
+    assert_eq!(len, 10);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn inplace_or_alloc_arrays_takes_combined_size_from_budget() {
+    set_thread_stack_budget(1024 * 1024);
+    inplace_or_alloc_arrays((128, 128), |_: (UninitializedSliceMemoryGuard<u32>, UninitializedSliceMemoryGuard<u64>)| {
+        // 1536 bytes are 96 chunks of 16 bytes
+        assert_eq!(thread_stack_budget(), 1024 * 1024 - 96 * 16);
+    });
+    set_thread_stack_budget(usize::MAX);
+}
@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use inplace_it::*;
+
+struct Tracked(Rc<Cell<usize>>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn inplace_chunks_passes_every_item_once() {
+    for &count in [0, 1, 31, 32, 33, 1000].iter() {
+        for &chunk_len in [1, 7, 32, 1000].iter() {
+            let mut collected = Vec::new();
+            let mut batches = 0;
+            inplace_chunks(0..count, chunk_len, |batch: &mut [usize]| {
+                assert!(!batch.is_empty() && batch.len() <= chunk_len);
+                batches += 1;
+                collected.extend_from_slice(batch);
+            }).map_err(|_| "Cannot place the batch").unwrap();
+            assert_eq!(collected, (0..count).collect::<Vec<_>>());
+            assert_eq!(batches, count.div_ceil(chunk_len));
+        }
+    }
+}
+
+#[test]
+fn inplace_chunks_drops_each_batch_before_next_one() {
+    let drops = Rc::new(Cell::new(0));
+    let mut batches = 0;
+    inplace_chunks((0..100).map(|_| Tracked(drops.clone())), 10, |batch| {
+        assert_eq!(batch.len(), 10);
+        assert_eq!(drops.get(), batches * 10);
+        batches += 1;
+    }).map_err(|_| "Cannot place the batch").unwrap();
+    assert_eq!(batches, 10);
+    assert_eq!(drops.get(), 100);
+}
+
+#[test]
+fn inplace_chunks_while_stops_on_break() {
+    let drops = Rc::new(Cell::new(0));
+    let mut iter = (0..100).map(|i| (i, Tracked(drops.clone())));
+    let result = inplace_chunks_while(iter.by_ref(), 8, |batch| {
+        match batch.iter().position(|(i, _)| *i == 20) {
+            Some(position) => ControlFlow::Break(position),
+            None => ControlFlow::Continue(()),
+        }
+    });
+    assert_eq!(result.ok(), Some(ControlFlow::Break(4)));
+    // Three batches are dropped, the rest items are not touched
+    assert_eq!(drops.get(), 24);
+    assert_eq!(iter.next().map(|(i, _)| i), Some(24));
+
+    let result = inplace_chunks_while(0..10, 4, |_: &mut [u32]| ControlFlow::<()>::Continue(()));
+    assert_eq!(result.ok(), Some(ControlFlow::Continue(())));
+}
+
+#[test]
+fn inplace_chunks_returns_untouched_iterator_if_batch_is_too_large() {
+    let result = inplace_chunks(0..10, 1_000_000, |_: &mut [u8]| unreachable!("Batch is not placed"));
+    match result {
+        Err((iter, _)) => assert_eq!(iter, 0..10),
+        Ok(()) => unreachable!("1000000 items are too much for the stack"),
+    }
+}
@@ -0,0 +1,37 @@
+use std::ffi::{CStr, CString};
+use inplace_it::*;
+
+#[test]
+fn inplace_cstr_appends_nul() {
+    for &len in [0, 1, 31, 32, 1000, 20_000].iter() {
+        let bytes = (0..len).map(|i| (i % 255 + 1) as u8).collect::<Vec<_>>();
+        let expected = CString::new(bytes.clone()).unwrap();
+        let result = inplace_cstr(&bytes, |string: &CStr| {
+            assert_eq!(string, &*expected);
+            string.to_bytes().len()
+        });
+        assert_eq!(result, Ok(len));
+    }
+}
+
+#[test]
+fn inplace_cstr_rejects_interior_nul() {
+    for &(bytes, position) in [(&b"\0"[..], 0), (b"abc\0", 3), (b"a\0b\0", 1)].iter() {
+        let result = inplace_cstr(bytes, |_: &CStr| unreachable!("String contains nul"));
+        let error = result.unwrap_err();
+        assert_eq!(error.nul_position(), position);
+        assert_eq!(error.to_string(), format!("nul byte found in provided data at position: {}", position));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn inplace_os_cstr_takes_paths() {
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+
+    let path = PathBuf::from("/usr").join("lib");
+    assert_eq!(inplace_os_cstr(&path, |string: &CStr| string.to_bytes() == b"/usr/lib"), Ok(true));
+    assert_eq!(inplace_os_cstr(OsStr::new("name"), |string: &CStr| string.to_bytes() == b"name"), Ok(true));
+    assert!(inplace_os_cstr("a\0b", |_: &CStr| ()).is_err());
+}
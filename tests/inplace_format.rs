@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::fmt;
+use inplace_it::*;
+
+#[test]
+fn inplace_format_passes_complete_string() {
+    for &len in [0, 1, 127, 128, 129, 1000, 16 * 1024, 60_000].iter() {
+        let expected = format!("{:>1$}", "x", len);
+        let result = inplace_format(format_args!("{:>1$}", "x", len), |string: &str| {
+            assert_eq!(string, expected);
+            string.len()
+        });
+        assert_eq!(result, expected.len());
+    }
+    let result = inplace_format(format_args!("no arguments"), |string: &str| string.to_owned());
+    assert_eq!(result, "no arguments");
+    let result = inplace_format(format_args!("{}-{}-{}", "κλειδί", 'ü', 1.5), |string: &str| string.to_owned());
+    assert_eq!(result, "κλειδί-ü-1.5");
+}
+
+/// Returns a longer string each time it's formatted.
+struct Growing(Cell<usize>);
+
+impl fmt::Display for Growing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.0.get();
+        self.0.set(len * 4);
+        for _ in 0..len {
+            f.write_str("a")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn inplace_format_handles_changing_output() {
+    let growing = Growing(Cell::new(100));
+    let len = inplace_format(format_args!("{}", growing), |string: &str| {
+        assert!(string.bytes().all(|byte| byte == b'a'));
+        string.len()
+    });
+    // Formatted into the initial buffer
+    assert_eq!(len, 100);
+
+    let growing = Growing(Cell::new(200));
+    let len = inplace_format(format_args!("{}", growing), |string: &str| string.len());
+    // Formatted twice: into the initial buffer and the larger one, which is spilled into `String`
+    assert_eq!(len, 200 * 4);
+    assert_eq!(growing.0.get(), 200 * 4 * 4);
+
+    let growing = Growing(Cell::new(20_000));
+    let len = inplace_format(format_args!("{}", growing), |string: &str| string.len());
+    // The same with the allocated buffer
+    assert_eq!(len, 20_000 * 4);
+    assert_eq!(growing.0.get(), 20_000 * 4 * 4);
+}
+
+struct Failing;
+
+impl fmt::Display for Failing {
+    fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+#[test]
+#[should_panic(expected = "a formatting trait implementation returned an error")]
+fn inplace_format_panics_on_formatting_error() {
+    inplace_format(format_args!("{}", Failing), |_: &str| ());
+}
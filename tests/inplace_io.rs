@@ -0,0 +1,107 @@
+#![cfg(feature = "std")]
+
+use std::io::{self, Read, Write};
+use inplace_it::*;
+
+/// Returns bytes by small pieces and interrupts every other read.
+struct Choppy<'a> {
+    bytes: &'a [u8],
+    interrupt: bool,
+}
+
+impl<'a> Read for Choppy<'a> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+        }
+        let len = buffer.len().min(self.bytes.len()).min(7);
+        buffer[..len].copy_from_slice(&self.bytes[..len]);
+        self.bytes = &self.bytes[len..];
+        Ok(len)
+    }
+}
+
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn read_exact_inplace_reads_exact_bytes() {
+    let bytes = data(50_000);
+    for &len in [0, 1, 100, 20_000, 50_000].iter() {
+        let mut reader = &bytes[..];
+        let read = read_exact_inplace(&mut reader, len, |read: &[u8]| read.to_vec()).unwrap();
+        assert_eq!(read, &bytes[..len]);
+        assert_eq!(reader.len(), bytes.len() - len);
+    }
+    let error = read_exact_inplace(&bytes[..10], 11, |_: &[u8]| ()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn read_to_end_inplace_reads_all_bytes() {
+    for &len in [0, 1, DEFAULT_IO_BUFFER_LEN - 1, DEFAULT_IO_BUFFER_LEN, DEFAULT_IO_BUFFER_LEN + 1, 100_000].iter() {
+        let bytes = data(len);
+        let read = read_to_end_inplace(Choppy { bytes: &bytes, interrupt: false }, |read: &[u8]| read.to_vec()).unwrap();
+        assert_eq!(read, bytes);
+    }
+}
+
+#[test]
+fn copy_inplace_copies_all_bytes() {
+    for &buffer_len in [1, 5, 4096, 100_000].iter() {
+        let bytes = data(30_000);
+        let mut writer = Vec::new();
+        let copied = copy_inplace(Choppy { bytes: &bytes, interrupt: false }, &mut writer, buffer_len).unwrap();
+        assert_eq!(copied, 30_000);
+        assert_eq!(writer, bytes);
+    }
+}
+
+/// Remembers length of the largest buffer it's asked to read into.
+struct Recording<'a> {
+    bytes: &'a [u8],
+    largest_buffer: usize,
+}
+
+impl<'a> Read for Recording<'a> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.largest_buffer = self.largest_buffer.max(buffer.len());
+        self.bytes.read(buffer)
+    }
+}
+
+#[test]
+fn io_buffers_are_zeroed_by_growing_chunks() {
+    let bytes = data(100);
+    let mut reader = Recording { bytes: &bytes, largest_buffer: 0 };
+    read_to_end_inplace(&mut reader, |read: &[u8]| assert_eq!(read, &bytes[..])).unwrap();
+    assert_eq!(reader.largest_buffer, 512);
+
+    let mut reader = Recording { bytes: &bytes, largest_buffer: 0 };
+    copy_inplace(&mut reader, io::sink(), 4096).unwrap();
+    assert_eq!(reader.largest_buffer, 512);
+
+    // Chunks grow while reader fills them
+    let bytes = data(5000);
+    let mut reader = Recording { bytes: &bytes, largest_buffer: 0 };
+    let mut writer = Vec::new();
+    copy_inplace(&mut reader, &mut writer, 4096).unwrap();
+    assert_eq!(writer, bytes);
+    assert_eq!(reader.largest_buffer, 4096);
+}
+
+#[test]
+fn inplace_cursor_writes_until_memory_is_full() {
+    inplace_or_alloc_array(10, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let mut cursor = InplaceCursor::new(guard.slice(..10).init(|_| 0));
+        cursor.write_all(b"abc").unwrap();
+        assert_eq!(cursor.write(b"defghijk").unwrap(), 7);
+        assert_eq!(cursor.write(b"x").unwrap(), 0);
+        assert_eq!(cursor.write_all(b"x").unwrap_err().kind(), io::ErrorKind::WriteZero);
+        assert_eq!(cursor.written(), b"abcdefghij");
+        let (written, rest) = cursor.into_parts();
+        assert_eq!((written.len(), rest.len()), (10, 0));
+    });
+}
@@ -0,0 +1,52 @@
+use std::fmt::Write;
+use inplace_it::*;
+
+#[test]
+fn inplace_string_works_like_string() {
+    for &capacity in [0, 4, 16, 256].iter() {
+        try_inplace_array(capacity, |guard: UninitializedSliceMemoryGuard<u8>| {
+            let mut string = InplaceString::new(guard.slice(..capacity));
+            let mut expected = String::new();
+
+            string.push_str("héllo");
+            expected.push_str("héllo");
+            string.push('😀');
+            expected.push('😀');
+            string.insert(0, 'ñ');
+            expected.insert(0, 'ñ');
+            string.insert(3, '!');
+            expected.insert(3, '!');
+            write!(string, " {}-{:?}", 42, "q").unwrap();
+            write!(expected, " {}-{:?}", 42, "q").unwrap();
+            assert_eq!(&*string, &*expected);
+            assert_eq!(string.pop(), expected.pop());
+            string.truncate(8);
+            expected.truncate(8);
+            assert_eq!(&*string, &*expected);
+            assert_eq!(string.spilled(), capacity < 21);
+            string.make_ascii_uppercase();
+            expected.make_ascii_uppercase();
+            assert_eq!(string.to_string(), expected);
+            assert_eq!(format!("{:?}", string), format!("{:?}", expected));
+            string.clear();
+            assert!(string.is_empty());
+            string.push_str("again");
+            assert_eq!(string.into_string(), "again");
+        }).map_err(|_| "Cannot inplace array").unwrap();
+    }
+}
+
+#[test]
+fn inplace_string_keeps_char_boundaries() {
+    inplace_or_alloc_array(16, |guard: UninitializedSliceMemoryGuard<u8>| {
+        let mut string = InplaceString::new(guard);
+        string.push_str("añb");
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| string.truncate(2))).is_err());
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| string.insert(2, 'x'))).is_err());
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| string.insert(5, 'x'))).is_err());
+        assert_eq!(&*string, "añb");
+        // Too large length keeps the string
+        string.truncate(100);
+        assert_eq!(&*string, "añb");
+    });
+}
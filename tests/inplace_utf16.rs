@@ -0,0 +1,33 @@
+use inplace_it::*;
+
+#[test]
+fn inplace_utf16_encodes_strings() {
+    let long = "añ😀".repeat(5000);
+    for string in ["", "ascii", "añ😀", "日本語", &long].iter() {
+        let expected = string.encode_utf16().collect::<Vec<_>>();
+        inplace_utf16(string, false, |units: &[u16]| assert_eq!(units, &*expected));
+        inplace_utf16(string, true, |units: &[u16]| {
+            assert_eq!(&units[..units.len() - 1], &*expected);
+            assert_eq!(units.last(), Some(&0));
+        });
+    }
+}
+
+#[test]
+fn inplace_utf8_from_utf16_decodes_units() {
+    let long = "añ😀".repeat(5000);
+    for string in ["", "ascii", "añ😀", "日本語", &long].iter() {
+        let units = string.encode_utf16().collect::<Vec<_>>();
+        let len = inplace_utf8_from_utf16(&units, |decoded| {
+            assert_eq!(decoded, Ok(*string));
+            decoded.unwrap().len()
+        });
+        assert_eq!(len, string.len());
+    }
+
+    // Lone low surrogate and unpaired high surrogate
+    for units in [&[0x61, 0xDC00, 0x62][..], &[0xD800, 0x61]].iter() {
+        let expected = std::char::decode_utf16(units.iter().cloned()).find_map(Result::err).unwrap();
+        inplace_utf8_from_utf16(units, |decoded| assert_eq!(decoded, Err(expected.clone())));
+    }
+}
@@ -0,0 +1,107 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use inplace_it::*;
+
+/// Counts its drops in shared counter.
+struct Tracked {
+    value: usize,
+    drops: Rc<Cell<usize>>,
+}
+
+impl Tracked {
+    fn new(value: usize, drops: &Rc<Cell<usize>>) -> Self {
+        Self { value, drops: drops.clone() }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+fn values(vec: &InplaceVec<Tracked>) -> Vec<usize> {
+    vec.iter().map(|item| item.value).collect()
+}
+
+#[test]
+fn inplace_vec_should_work_like_vec() {
+    for &capacity in [0, 1, 4, 8, 32].iter() {
+        try_inplace_array(capacity, |guard: UninitializedSliceMemoryGuard<usize>| {
+            let guard = guard.slice(..capacity);
+            let mut vec = InplaceVec::new(guard);
+            let mut expected = Vec::new();
+
+            for i in 0..10 {
+                vec.push(i);
+                expected.push(i);
+            }
+            vec.insert(3, 100);
+            expected.insert(3, 100);
+            vec.insert(vec.len(), 200);
+            expected.insert(expected.len(), 200);
+            assert_eq!(vec.remove(0), expected.remove(0));
+            assert_eq!(vec.pop(), expected.pop());
+            vec.retain(|item| item % 3 != 0);
+            expected.retain(|item| item % 3 != 0);
+            assert_eq!(&*vec, &*expected);
+
+            assert_eq!(vec.drain(1..3).collect::<Vec<_>>(), expected.drain(1..3).collect::<Vec<_>>());
+            assert_eq!(vec.drain(..1).rev().collect::<Vec<_>>(), expected.drain(..1).rev().collect::<Vec<_>>());
+            vec.extend(20..25);
+            expected.extend(20..25);
+            vec.truncate(4);
+            expected.truncate(4);
+            assert_eq!(&*vec, &*expected);
+            assert_eq!(vec.spilled(), capacity < 15);
+            assert_eq!(vec.into_vec(), expected);
+        }).map_err(|_| "Cannot inplace array").unwrap();
+    }
+}
+
+#[test]
+fn inplace_vec_should_drop_every_element_once() {
+    let drops = Rc::new(Cell::new(0));
+    try_inplace_array(16, |guard: UninitializedSliceMemoryGuard<Tracked>| {
+        let mut vec = InplaceVec::new(guard.slice(..16));
+        vec.extend((0..16).map(|i| Tracked::new(i, &drops)));
+        assert!(!vec.spilled());
+
+        vec.truncate(14);
+        assert_eq!(drops.get(), 2);
+        vec.retain(|item| item.value % 2 == 0);
+        assert_eq!(drops.get(), 9);
+        assert_eq!(values(&vec), [0, 2, 4, 6, 8, 10, 12]);
+
+        // Partially consumed drain drops the rest of drained elements
+        let mut drain = vec.drain(1..5);
+        assert_eq!(drain.next().map(|item| item.value), Some(2));
+        drop(drain);
+        assert_eq!(drops.get(), 13);
+        assert_eq!(values(&vec), [0, 10, 12]);
+
+        // Spilled elements are moved, not copied
+        vec.extend((0..20).map(|i| Tracked::new(i, &drops)));
+        assert!(vec.spilled());
+        assert_eq!(drops.get(), 13);
+        assert_eq!(vec.len(), 23);
+    }).map_err(|_| "Cannot inplace array").unwrap();
+    assert_eq!(drops.get(), 36);
+}
+
+#[test]
+fn inplace_vec_retain_should_not_double_drop_on_panic() {
+    let drops = Rc::new(Cell::new(0));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        try_inplace_array(8, |guard: UninitializedSliceMemoryGuard<Tracked>| {
+            let mut vec = InplaceVec::new(guard.slice(..8));
+            vec.extend((0..8).map(|i| Tracked::new(i, &drops)));
+            vec.retain(|item| {
+                assert!(item.value != 5);
+                item.value % 2 == 0
+            });
+        }).map_err(|_| "Cannot inplace array").unwrap();
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 8);
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "scratch-pool")]
+
+use inplace_it::*;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Remembers sizes of the largest allocation and deallocation of each thread.
+struct RecordingAllocator;
+
+thread_local!(static LARGEST_ALLOCATION: Cell<usize> = const { Cell::new(0) });
+thread_local!(static LARGEST_DEALLOCATION: Cell<usize> = const { Cell::new(0) });
+
+unsafe impl GlobalAlloc for RecordingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = LARGEST_ALLOCATION.try_with(|largest| largest.set(largest.get().max(layout.size())));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LARGEST_DEALLOCATION.try_with(|largest| largest.set(largest.get().max(layout.size())));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: RecordingAllocator = RecordingAllocator;
+
+fn heap_pointer(size: usize) -> *const u64 {
+    alloc_array(size, |guard: UninitializedSliceMemoryGuard<u64>| guard.init(|i| i as u64).as_ptr())
+}
+
+#[test]
+fn scratch_pool_reuses_buffers_of_same_class() {
+    clear_scratch_pool();
+    let first = heap_pointer(3000);
+    // 3000 u64 are rounded up to 32 KiB
+    assert_eq!(scratch_pool_size(), 32 * 1024);
+    // 2500 u64 are in the same class
+    assert_eq!(heap_pointer(2500), first);
+    assert_eq!(scratch_pool_size(), 32 * 1024);
+
+    // Nested allocations take different buffers
+    alloc_array(3000, |outer: UninitializedSliceMemoryGuard<u64>| {
+        assert_eq!(scratch_pool_size(), 0);
+        let outer = outer.init(|i| i as u64);
+        assert_eq!(outer.as_ptr(), first);
+        assert_ne!(heap_pointer(3000), first);
+    });
+    assert_eq!(scratch_pool_size(), 64 * 1024);
+
+    trim_scratch_pool(40 * 1024);
+    assert_eq!(scratch_pool_size(), 32 * 1024);
+    clear_scratch_pool();
+    assert_eq!(scratch_pool_size(), 0);
+}
+
+#[test]
+fn scratch_pool_keeps_no_more_than_capacity() {
+    clear_scratch_pool();
+    heap_pointer(DEFAULT_SCRATCH_POOL_CAPACITY);
+    assert_eq!(scratch_pool_size(), 0);
+    heap_pointer(DEFAULT_SCRATCH_POOL_CAPACITY / 8);
+    assert_eq!(scratch_pool_size(), DEFAULT_SCRATCH_POOL_CAPACITY);
+}
+
+#[test]
+fn scratch_pool_allocates_too_large_buffers_as_is() {
+    clear_scratch_pool();
+    LARGEST_ALLOCATION.with(|largest| largest.set(0));
+    // Rounded up, it would take twice as much
+    let size = MAX_SCRATCH_BUFFER_SIZE + 1;
+    alloc_array(size, |guard: UninitializedSliceMemoryGuard<u8>| assert_eq!(guard.len(), size));
+    assert_eq!(LARGEST_ALLOCATION.with(Cell::get), size);
+    assert_eq!(scratch_pool_size(), 0);
+
+    LARGEST_ALLOCATION.with(|largest| largest.set(0));
+    alloc_array(10_000, |guard: UninitializedSliceMemoryGuard<u8>| assert_eq!(guard.len(), 10_000));
+    assert_eq!(LARGEST_ALLOCATION.with(Cell::get), 16 * 1024);
+    assert_eq!(scratch_pool_size(), 16 * 1024);
+    clear_scratch_pool();
+}
+
+#[test]
+fn scratch_pool_buffers_can_be_deallocated_on_other_thread() {
+    for &size in &[10_000, MAX_SCRATCH_BUFFER_SIZE + 1] {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let ptr = std::thread::spawn(move || ThreadScratchPool.allocate(layout).unwrap().as_ptr() as usize)
+            .join()
+            .unwrap();
+
+        clear_scratch_pool();
+        LARGEST_DEALLOCATION.with(|largest| largest.set(0));
+        unsafe { ThreadScratchPool.deallocate(core::ptr::NonNull::new(ptr as *mut u8).unwrap(), layout) };
+        clear_scratch_pool();
+        let allocated = ThreadScratchPool.allocated_layout(layout).size();
+        assert_eq!(LARGEST_DEALLOCATION.with(Cell::get), allocated);
+    }
+    assert_eq!(ThreadScratchPool.allocated_layout(Layout::from_size_align(10_000, 8).unwrap()).size(), 16 * 1024);
+}
@@ -0,0 +1,58 @@
+use inplace_it::*;
+
+fn check_size_classes<S: SizeClasses>() {
+    let mut previous_len = 0;
+    for size in 0..=65536 {
+        let len = S::class_len(size).expect("Sizes up to 65536 should be placed");
+        assert!(len >= size);
+        assert!(len >= previous_len);
+        previous_len = len;
+    }
+    assert_eq!(S::class_len(65537), None);
+
+    for size in (0..=4096).chain([8000, 20000, 65536].iter().cloned()) {
+        let placed = S::place(size, |guard: UninitializedSliceMemoryGuard<u8>| guard.len());
+        assert_eq!(placed.ok(), S::class_len(size));
+    }
+    assert!(S::place(65537, |guard: UninitializedSliceMemoryGuard<u8>| guard.len()).is_err());
+}
+
+#[test]
+fn size_classes_are_consistent() {
+    check_size_classes::<Linear32>();
+    check_size_classes::<PowersOfTwo>();
+    check_size_classes::<ExactThenGeometric>();
+}
+
+fn check_size_classes_with_limit<S: SizeClasses>() {
+    for size in (0..=4096).chain([8000, 20000, 65536].iter().cloned()) {
+        let placed = S::place_with_limit::<u32, { 16 * 1024 }, _, _>(size, |guard: UninitializedSliceMemoryGuard<u32>| guard.len());
+        let expected = S::class_len(size).filter(|len| len * 4 <= 16 * 1024);
+        assert_eq!(placed.ok(), expected);
+    }
+}
+
+#[test]
+fn size_classes_are_consistent_with_limit() {
+    check_size_classes_with_limit::<Linear32>();
+    check_size_classes_with_limit::<PowersOfTwo>();
+    check_size_classes_with_limit::<ExactThenGeometric>();
+}
+
+#[test]
+fn size_classes_are_limited_by_rounded_size() {
+    // 33 items of 256 bytes are 8448 bytes, but they are rounded up to 64 items (16 KiB) by `Linear32`
+    let placed = try_inplace_array_with_size_classes::<[u8; 256], Linear32, 8448, _, _>(33, |guard| guard.len());
+    assert!(placed.is_err());
+    // and left as is by `ExactThenGeometric`
+    let placed = try_inplace_array_with_size_classes::<[u8; 256], ExactThenGeometric, 8448, _, _>(33, |guard| guard.len());
+    assert_eq!(placed.ok(), Some(33));
+}
+
+#[test]
+fn size_classes_are_limited_by_the_same_size_with_compact_frames() {
+    // One item of 16385 bytes is placed in the frame of 24 KiB with `compact` feature,
+    // but the limit is still checked against 16385 bytes
+    let placed = try_inplace_array_with_size_classes::<[u8; 16385], ExactThenGeometric, 20_000, _, _>(1, |guard| guard.len());
+    assert_eq!(placed.ok(), Some(1));
+}
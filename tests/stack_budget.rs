@@ -0,0 +1,67 @@
+use inplace_it::*;
+
+#[test]
+fn stack_budget_is_shared_by_nested_placements() {
+    let budget = StackBudget::new(16 * 1024);
+
+    /// Pushes length of each array and whether it's placed on the stack.
+    fn nest(budget: &StackBudget, depth: usize, arrays: &mut Vec<(usize, bool)>) {
+        if depth == 0 {
+            return;
+        }
+        let local = 0u8;
+        inplace_or_alloc_array_with_budget(budget, 1024, |guard: UninitializedSliceMemoryGuard<u32>| {
+            let guard = guard.init(|_| 0);
+            let on_stack = (guard.as_ptr() as usize).abs_diff(&local as *const u8 as usize) < 1024 * 1024;
+            arrays.push((guard.len(), on_stack));
+            nest(budget, depth - 1, arrays);
+        });
+    }
+
+    let mut arrays = Vec::new();
+    nest(&budget, 6, &mut arrays);
+    // 1024 u32 take 4 KiB, so only 4 of them are placed
+    assert_eq!(arrays, [(1024, true), (1024, true), (1024, true), (1024, true), (1024, false), (1024, false)]);
+    assert_eq!(budget.remaining(), 16 * 1024);
+}
+
+#[test]
+fn stack_budget_is_given_back_on_panic() {
+    let budget = StackBudget::new(16 * 1024);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        try_inplace_array_with_budget(&budget, 1000, |_: UninitializedSliceMemoryGuard<u32>| {
+            panic!("Consumer panics");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(budget.remaining(), 16 * 1024);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn thread_stack_budget_is_shared_by_nested_placements() {
+    set_thread_stack_budget(8 * 1024);
+    let result = try_inplace_array(1024, |_: UninitializedSliceMemoryGuard<u32>| {
+        assert_eq!(thread_stack_budget(), 4 * 1024);
+        assert!(try_inplace_array(1024, |_: UninitializedSliceMemoryGuard<u32>| {
+            assert_eq!(thread_stack_budget(), 0);
+            assert!(try_inplace_array(1, |_: UninitializedSliceMemoryGuard<u32>| ()).is_err());
+        }).is_ok());
+        assert_eq!(thread_stack_budget(), 4 * 1024);
+    });
+    assert!(result.is_ok());
+    assert_eq!(thread_stack_budget(), 8 * 1024);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn thread_stack_budget_set_inside_placement_counts_running_placements() {
+    set_thread_stack_budget(8 * 1024);
+    let result = try_inplace_array(1024, |_: UninitializedSliceMemoryGuard<u32>| {
+        set_thread_stack_budget(6 * 1024);
+        assert_eq!(thread_stack_budget(), 2 * 1024);
+    });
+    assert!(result.is_ok());
+    assert_eq!(thread_stack_budget(), 6 * 1024);
+    set_thread_stack_budget(usize::MAX);
+}
@@ -0,0 +1,41 @@
+#![cfg(all(feature = "std", target_os = "linux"))]
+
+use inplace_it::*;
+
+#[inline(never)]
+fn recurse_until<R>(remaining: usize, f: &mut dyn FnMut() -> R) -> R {
+    let frame = [0u8; 512];
+    std::hint::black_box(&frame);
+    if remaining_stack().unwrap() > remaining {
+        let result = recurse_until(remaining, f);
+        std::hint::black_box(&frame);
+        result
+    } else {
+        f()
+    }
+}
+
+#[test]
+fn stack_headroom_is_unknown_for_foreign_threads() {
+    // Test threads are neither main thread, nor spawned with `spawn_with_stack_bounds`
+    assert_eq!(remaining_stack(), None);
+}
+
+#[test]
+fn inplace_falls_back_to_heap_near_the_end_of_stack() {
+    const STACK_SIZE: usize = 512 * 1024;
+
+    spawn_with_stack_bounds(std::thread::Builder::new().stack_size(STACK_SIZE), || {
+        let remaining = remaining_stack().unwrap();
+        assert!(remaining < STACK_SIZE && remaining > STACK_SIZE / 2);
+
+        let margin = stack_safety_margin();
+        assert!(try_inplace_array(16 * 1024, |guard: UninitializedSliceMemoryGuard<u8>| guard.len()).is_ok());
+
+        recurse_until(margin + 8 * 1024, &mut || {
+            assert!(try_inplace_array(16 * 1024, |guard: UninitializedSliceMemoryGuard<u8>| guard.len()).is_err());
+            assert_eq!(inplace_or_alloc_array(16 * 1024, |guard: UninitializedSliceMemoryGuard<u8>| guard.len()), 16 * 1024);
+            assert!(try_inplace_array(1024, |guard: UninitializedSliceMemoryGuard<u8>| guard.len()).is_ok());
+        });
+    }).unwrap().join().unwrap();
+}
@@ -12,14 +12,10 @@ fn get_stack_pointer_value() -> usize {
 }
 
 fn calculate_stack_consumption(begin: usize, end: usize) -> usize {
-    if begin >= end {
-        begin - end
-    } else {
-        end - begin
-    }
+    begin.abs_diff(end)
 }
 
-/// This test measures stack memory consumption from 0 to 4096 items by step 32
+/// This test measures stack memory consumption from 0 to the stack limit (in items) by step 32
 /// Then, it calculates "tangent of an angle" (y/x) from "point of zero" (0 items and it's stack size).
 ///
 /// It bad cases, when compiler optimizes `try_inplace_array` function so that it doesn't make sense,
@@ -40,7 +36,7 @@ fn stack_memory_should_allocate_no_more_than_needed() {
             let mem = mem.init(|i| i);
             let mut sum = 0usize;
             for i in mem.iter() {
-                sum += *i as usize;
+                sum += *i;
             }
             // To sure sum operation was not optimized to no-op
             let len = mem.len();
@@ -49,8 +45,7 @@ fn stack_memory_should_allocate_no_more_than_needed() {
             } else {
                 0
             });
-            let stack_consumption = calculate_stack_consumption(begin, end);
-            stack_consumption
+            calculate_stack_consumption(begin, end)
         });
         match result {
             Ok(result) => result,
@@ -77,7 +72,8 @@ fn stack_memory_should_allocate_no_more_than_needed() {
         max - min
     }
 
-    let stack_sizes = (0..=4096).step_by(32)
+    let max_length = DEFAULT_STACK_LIMIT_BYTES / std::mem::size_of::<usize>();
+    let stack_sizes = (0..=max_length).step_by(32)
         .map(|length| {
             let stack_size = inplace_and_sum(length);
             const USIZE_LAYOUT: usize = std::mem::size_of::<usize>() + std::mem::align_of::<usize>(); // usize layout coefficient
@@ -101,3 +97,83 @@ fn stack_memory_should_allocate_no_more_than_needed() {
 
     assert!(differentials_borders_dispersion <= 2.0);
 }
+
+#[cfg(all(feature = "alloca", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[test]
+fn alloca_moves_stack_pointer_by_exact_size() {
+    const LIMIT: usize = 1024 * 1024;
+
+    #[inline(never)]
+    fn alloca_and_measure(size: usize) -> usize {
+        let begin = get_stack_pointer_value();
+        let result = try_inplace_array_with_size_classes::<usize, Alloca, LIMIT, _, _>(size, |mem| {
+            let end = get_stack_pointer_value();
+            assert_eq!(mem.len(), size);
+            let mem = mem.init(|i| i);
+            assert_eq!(mem.iter().sum::<usize>(), if size > 0 { size * (size - 1) / 2 } else { 0 });
+            calculate_stack_consumption(begin, end)
+        });
+        match result {
+            Ok(result) => result,
+            Err(_) => panic!("Inplace should never fail is this test"),
+        }
+    }
+
+    let overhead = alloca_and_measure(0);
+    for size in (1..=LIMIT / std::mem::size_of::<usize>()).step_by(997) {
+        let consumption = alloca_and_measure(size) - overhead;
+        let bytes = size * std::mem::size_of::<usize>();
+        // Only alignment of the stack pointer may be added
+        assert!(consumption + 16 >= bytes && consumption <= bytes + 16, "{} bytes took {} bytes of stack", bytes, consumption);
+    }
+}
+
+#[cfg(all(feature = "alloca", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[test]
+fn alloca_probes_large_arrays() {
+    const LIMIT: usize = 8 * 1024 * 1024;
+
+    let sum = std::thread::Builder::new()
+        .stack_size(16 * 1024 * 1024)
+        .spawn(|| {
+            inplace_or_alloc_array_with_size_classes::<u8, Alloca, LIMIT, _, _>(LIMIT, |mem| {
+                assert_eq!(mem.len(), LIMIT);
+                mem.init(|i| (i % 2) as u8).iter().map(|i| *i as usize).sum::<usize>()
+            })
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(sum, LIMIT / 2);
+}
+
+#[cfg(all(feature = "alloca", target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+#[test]
+fn alloca_unwinds_panics_of_consumer() {
+    use std::{cell::Cell, panic::{catch_unwind, AssertUnwindSafe}};
+
+    struct SetOnDrop<'a>(&'a Cell<bool>);
+
+    impl Drop for SetOnDrop<'_> {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    let dropped = Cell::new(false);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let captured = SetOnDrop(&dropped);
+        inplace_or_alloc_array_with_size_classes::<usize, Alloca, DEFAULT_STACK_LIMIT_BYTES, _, _>(1000, move |mem| {
+            let _captured = captured;
+            assert_eq!(mem.len(), 1000);
+            mem.init(|i| i);
+            panic!("Consumer panics");
+        })
+    }));
+    assert!(result.is_err());
+    assert!(dropped.get());
+
+    // The stack pointer is restored, so placing works as before
+    let len = try_inplace_array_with_size_classes::<usize, Alloca, DEFAULT_STACK_LIMIT_BYTES, _, _>(1000, |mem| mem.len());
+    assert_eq!(len.ok(), Some(1000));
+}